@@ -1,11 +1,11 @@
 use anyhow::Error;
 
 use clap::Parser;
-use tai_lib::common::command::run_command;
+use tai_lib::common::command::{clean_command, config_command, devices_command, run_command};
 
 mod opts;
 
-use opts::Options;
+use opts::{Options, Request};
 use tracing_subscriber::{fmt::format::FmtSpan, prelude::*, EnvFilter};
 
 fn main() -> Result<(), Error> {
@@ -16,11 +16,17 @@ fn main() -> Result<(), Error> {
         .with_level(false)
         .without_time()
         .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
+        // stdout only carries the events of `--message-format json`
+        .with_writer(std::io::stderr)
         .finish()
         .init();
 
     let opt = Options::from_args();
-    let requested_opt: tai_lib::common::opts::Options = opt.into();
 
-    run_command(requested_opt)
+    match opt.into_request()? {
+        Request::Run(requested_opt) => run_command(requested_opt),
+        Request::ListDevices(requested_opt) => devices_command(requested_opt),
+        Request::PrintConfig(config) => config_command(config),
+        Request::Clean(requested_opt) => clean_command(requested_opt),
+    }
 }
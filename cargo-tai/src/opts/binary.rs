@@ -1,7 +1,9 @@
+use std::time::Duration;
+
 use clap::Parser;
 use tai_lib::common::opts;
 
-use super::parse_key_val;
+use super::{parse_duration, parse_key_val};
 
 #[derive(Parser, Debug)]
 pub struct BinaryOptions {
@@ -20,10 +22,69 @@ pub struct BinaryOptions {
     /// `cargo-tai test --envs TAI_1=1 TAI_2=2`
     #[clap(short, long, parse(try_from_str = parse_key_val))]
     pub envs: Option<Vec<(String, String)>>,
+
+    /// Kill a test/benchmark binary that is still running after the given duration
+    /// and report it as timed out. Format: `<number>[ms|s|m|h]`
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai test --timeout 5m`
+    #[clap(long, parse(try_from_str = parse_duration))]
+    pub timeout: Option<Duration>,
+
+    /// Only run the tests/benchmarks whose name contains the filter.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai test --filter tests::read_file`
+    #[clap(long, allow_hyphen_values = true)]
+    pub filter: Option<String>,
+
+    /// Only run the tests/benchmarks whose name is exactly the filter.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai test --filter tests::read_file --exact`
+    #[clap(long, requires = "filter")]
+    pub exact: bool,
+
+    /// Only run the ignored tests.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --ignored`
+    #[clap(long)]
+    pub ignored: bool,
+
+    /// Skip the tests/benchmarks whose name contains the filter. Can be used multiple times.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --skip slow --skip "network tests"`
+    #[clap(long, multiple_occurrences = true, allow_hyphen_values = true)]
+    pub skip: Option<Vec<String>>,
 }
 
 impl From<BinaryOptions> for Option<opts::BinaryOptions> {
-    fn from(BinaryOptions { args, envs }: BinaryOptions) -> Self {
-        Some(opts::BinaryOptions { args, envs })
+    fn from(
+        BinaryOptions {
+            args,
+            envs,
+            timeout,
+            filter,
+            exact,
+            ignored,
+            skip,
+        }: BinaryOptions,
+    ) -> Self {
+        Some(opts::BinaryOptions {
+            args,
+            envs,
+            timeout,
+            filter,
+            exact,
+            ignored,
+            skip,
+        })
     }
 }
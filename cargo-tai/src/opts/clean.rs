@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use tai_lib::common::opts;
+
+#[derive(Parser, Debug)]
+pub struct CleanCommand {
+    /// Also remove the resource cache from all Android devices.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai clean --device`
+    #[clap(long)]
+    pub device: bool,
+
+    /// The path to the android sdk. If not set, the sdk is discovered like for `tests`
+    /// and `adb` is looked up in `PATH` as a fallback.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai clean --device --android-sdk ~/Library/Android/sdk`
+    #[clap(long = "android-sdk", env = "ANDROID_SDK_HOME")]
+    pub sdk: Option<PathBuf>,
+
+    /// The path to the android ndk.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai clean --device --android-ndk ~/Library/Android/sdk/ndk/22.1.7171670`
+    #[clap(long = "android-ndk", env = "ANDROID_NDK_HOME")]
+    pub ndk: Option<PathBuf>,
+
+    /// Arguments that are passed to `cargo`.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai clean -- --manifest-path ./test-project/Cargo.toml`
+    #[clap(setting(clap::ArgSettings::Last))]
+    pub cargo_args: Vec<String>,
+}
+
+impl From<CleanCommand> for opts::CleanOptions {
+    fn from(
+        CleanCommand {
+            device,
+            sdk,
+            ndk,
+            cargo_args,
+        }: CleanCommand,
+    ) -> Self {
+        opts::CleanOptions {
+            device,
+            cargo_args,
+            android_sdk: sdk,
+            android_ndk: ndk,
+        }
+    }
+}
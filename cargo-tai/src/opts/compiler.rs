@@ -6,18 +6,34 @@ use tai_lib::common::opts;
 #[derive(Parser, Debug)]
 pub struct CompilerOptions {
     /// Build for the target triples
-    #[clap(long, parse(try_from_str = parse_target), long_help =
-    r"Build for the target triples
+    #[clap(
+        long,
+        parse(try_from_str = parse_target),
+        multiple_occurrences = true,
+        use_value_delimiter = true,
+        required_unless_present = "all-android-targets",
+        long_help =
+    r"Build for the target triples. Can be repeated or comma-separated to run
+the command for several targets one after another.
 
 Supported targets:
 - `x86_64-apple-ios` (macOS only)
 - `aarch64-apple-ios` (macOS only)
+- `aarch64-apple-ios-sim` (macOS only)
 - `x86_64-linux-android`
 - `aarch64-linux-android`
 - `i686-linux-android`
 - `armv7-linux-androideabi`"
     )]
-    pub target: TargetInfo<'static>,
+    pub target: Vec<TargetInfo<'static>>,
+
+    /// Build for all Android targets
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --all-android-targets --android-api-lvl 21`
+    #[clap(long, conflicts_with = "target")]
+    pub all_android_targets: bool,
 
     /// Arguments that are passed to `cargo`. See `cargo build --help`.
     ///
@@ -28,16 +44,34 @@ Supported targets:
     pub cargo_args: Vec<String>,
 }
 
+const ANDROID_TARGETS: [&str; 4] = [
+    "aarch64-linux-android",
+    "armv7-linux-androideabi",
+    "i686-linux-android",
+    "x86_64-linux-android",
+];
+
 fn parse_target(src: &str) -> Result<TargetInfo<'static>, Error> {
     let target = get_builtin_target_by_triple(src).ok_or_else(|| anyhow!("unsupported target"))?;
     Ok(target.to_owned())
 }
 
-impl From<CompilerOptions> for opts::CompilerOptions {
+/// One [`opts::CompilerOptions`] per target.
+impl From<CompilerOptions> for Vec<opts::CompilerOptions> {
     fn from(options: CompilerOptions) -> Self {
-        opts::CompilerOptions {
-            target: options.target,
-            cargo_args: options.cargo_args,
-        }
+        let targets = match options.all_android_targets {
+            true => ANDROID_TARGETS
+                .iter()
+                .map(|triple| get_builtin_target_by_triple(triple).unwrap().to_owned())
+                .collect(),
+            false => options.target,
+        };
+        targets
+            .into_iter()
+            .map(|target| opts::CompilerOptions {
+                target,
+                cargo_args: options.cargo_args.clone(),
+            })
+            .collect()
     }
 }
@@ -0,0 +1,112 @@
+use clap::Parser;
+use tai_lib::common::opts;
+
+#[derive(Parser, Debug)]
+pub struct DeviceOptions {
+    /// Only run on the devices whose serial/UDID, name or model matches the pattern.
+    /// `*` matches any characters, `api=<level>` matches the API level of Android devices.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --device "Pixel 6" --device emulator-*`
+    #[clap(long = "device")]
+    pub devices: Vec<String>,
+
+    /// Run on all (matching) devices. This is the default.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --all-devices`
+    #[clap(long, conflicts_with = "first-device")]
+    pub all_devices: bool,
+
+    /// Only run on the first (matching) device.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --first-device`
+    #[clap(long)]
+    pub first_device: bool,
+
+    /// Run on an iOS simulator of the device type and runtime. A matching simulator is booted
+    /// or, if none exists, created. Without a runtime, the newest iOS runtime is used.
+    /// The names are the ones of `xcrun simctl list`. Format: `<device type>[/<runtime>]`
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --target x86_64-apple-ios --simulator "iPhone 14/iOS 16.2"`
+    #[clap(long, value_name = "DEVICE-TYPE/RUNTIME")]
+    pub simulator: Option<String>,
+
+    /// Boot an iOS simulator if none is booted.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --target x86_64-apple-ios --boot`
+    #[clap(long)]
+    pub boot: bool,
+
+    /// Create a new simulator for `--simulator` and delete it after the run.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --target x86_64-apple-ios --simulator "iPhone 14" --ephemeral-simulator`
+    #[clap(long, requires = "simulator")]
+    pub ephemeral_simulator: bool,
+
+    /// Shut down the simulator after the run if it was booted by `--simulator` or `--boot`.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --target x86_64-apple-ios --boot --shutdown-simulator`
+    #[clap(long)]
+    pub shutdown_simulator: bool,
+
+    /// Start an Android emulator of the AVD, run on it and kill it after the run.
+    /// An emulator that already runs the AVD is used and not killed.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --target x86_64-linux-android --avd Pixel_6_API_33`
+    #[clap(long, value_name = "NAME")]
+    pub avd: Option<String>,
+
+    /// Run the emulator of `--avd` without a window.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --target x86_64-linux-android --avd Pixel_6_API_33 --headless`
+    #[clap(long, requires = "avd")]
+    pub headless: bool,
+}
+
+impl From<DeviceOptions> for opts::DeviceOptions {
+    fn from(
+        DeviceOptions {
+            devices,
+            all_devices: _,
+            first_device,
+            simulator,
+            boot,
+            ephemeral_simulator,
+            shutdown_simulator,
+            avd,
+            headless,
+        }: DeviceOptions,
+    ) -> Self {
+        let simulator = match (simulator, boot) {
+            (None, false) => None,
+            (spec, _) => Some(opts::SimulatorOptions {
+                spec,
+                ephemeral: ephemeral_simulator,
+                shutdown: shutdown_simulator,
+            }),
+        };
+        opts::DeviceOptions {
+            patterns: devices,
+            first: first_device,
+            simulator,
+            emulator: avd.map(|avd| opts::EmulatorOptions { avd, headless }),
+        }
+    }
+}
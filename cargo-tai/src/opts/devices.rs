@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use tai_lib::common::opts;
+
+#[derive(Parser, Debug)]
+pub struct DevicesCommand {
+    /// Print the devices as JSON.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai devices --json`
+    #[clap(long)]
+    pub json: bool,
+
+    /// The path to the android sdk. If not set, the sdk is discovered like for `tests`
+    /// and `adb` is looked up in `PATH` as a fallback.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai devices --android-sdk ~/Library/Android/sdk`
+    #[clap(long = "android-sdk", env = "ANDROID_SDK_HOME")]
+    pub sdk: Option<PathBuf>,
+
+    /// The path to the android ndk.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai devices --android-ndk ~/Library/Android/sdk/ndk/22.1.7171670`
+    #[clap(long = "android-ndk", env = "ANDROID_NDK_HOME")]
+    pub ndk: Option<PathBuf>,
+}
+
+impl From<DevicesCommand> for opts::ListDevicesOptions {
+    fn from(DevicesCommand { json, sdk, ndk }: DevicesCommand) -> Self {
+        opts::ListDevicesOptions {
+            json,
+            android_sdk: sdk,
+            android_ndk: ndk,
+        }
+    }
+}
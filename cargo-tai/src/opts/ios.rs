@@ -1,18 +1,10 @@
 use clap::Parser;
 use std::path::PathBuf;
-use tai_lib::common::opts::{self};
 
 #[derive(Parser, Debug)]
 pub struct IosOptions {
-    #[structopt(
-        long = "ios-mobile-provision",
-        required_if("target", "aarch64-apple-ios")
-    )]
+    /// The mobile provision profile: only required when "target" is "aarch64-apple-ios"
+    /// and `ios-mobile-provision` is not set in Tai.toml or `[package.metadata.tai]`.
+    #[structopt(long = "ios-mobile-provision")]
     pub mobile_provision: Option<PathBuf>,
 }
-
-impl From<IosOptions> for Option<opts::IosOptions> {
-    fn from(IosOptions { mobile_provision }: IosOptions) -> Self {
-        mobile_provision.map(|mobile_provision| opts::IosOptions { mobile_provision })
-    }
-}
@@ -1,19 +1,29 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Error};
+use cfg_expr::targets::Os;
 use clap::Parser;
 use tai_lib::common::{
-    command::Command,
+    command::{Command, Platform},
+    config::{Config, TargetConfig},
     opts::{self},
 };
 
 pub mod android;
 pub mod binary;
+pub mod clean;
 pub mod cli;
 pub mod compiler;
+pub mod device;
+pub mod devices;
 pub mod ios;
 pub mod resource;
+pub mod run;
 
 use self::{
-    android::AndroidOptions, binary::BinaryOptions, cli::CliOptions, compiler::CompilerOptions,
-    ios::IosOptions, resource::ResourceOptions,
+    android::AndroidOptions, binary::BinaryOptions, clean::CleanCommand, cli::CliOptions,
+    compiler::CompilerOptions, device::DeviceOptions, devices::DevicesCommand, ios::IosOptions,
+    resource::ResourceOptions, run::RunOptions,
 };
 
 #[derive(Parser, Debug)]
@@ -26,6 +36,26 @@ pub enum Options {
     Benches(LocalRun),
     #[structopt(about = "Test all tests")]
     Tests(LocalRun),
+    #[structopt(about = "List all devices, emulators and simulators that cargo-tai can reach")]
+    Devices(DevicesCommand),
+    #[structopt(
+        about = "Print the configuration that results from `Tai.toml`, `[package.metadata.tai]` and the flags"
+    )]
+    Config(LocalRun),
+    #[structopt(
+        about = "Remove the bundles of cargo-tai and, with `--device`, the resource caches on the devices"
+    )]
+    Clean(CleanCommand),
+}
+
+/// What `cargo-tai` has been asked to do.
+pub enum Request {
+    /// The options of the command for each target
+    Run(Vec<opts::Options>),
+    ListDevices(opts::ListDevicesOptions),
+    /// The effective configuration of each target
+    PrintConfig(Config),
+    Clean(opts::CleanOptions),
 }
 
 #[derive(Parser, Debug)]
@@ -42,6 +72,12 @@ pub struct LocalRun {
     #[structopt(flatten)]
     binary: BinaryOptions,
 
+    #[structopt(flatten)]
+    run: RunOptions,
+
+    #[structopt(flatten)]
+    devices: DeviceOptions,
+
     #[structopt(flatten)]
     android: AndroidOptions,
 
@@ -49,29 +85,119 @@ pub struct LocalRun {
     ios: IosOptions,
 }
 
-impl From<Options> for opts::Options {
-    fn from(opt: Options) -> Self {
-        match opt {
-            Options::Bench(opts) => from_local_run(Command::Bench, opts),
-            Options::Test(opts) => from_local_run(Command::Test, opts),
-            Options::Benches(opts) => from_local_run(Command::Benches, opts),
-            Options::Tests(opts) => from_local_run(Command::Tests, opts),
-        }
+impl Options {
+    /// Merges the flags with the configuration of the project. Flags take precedence.
+    pub fn into_request(self) -> Result<Request, Error> {
+        let config = match &self {
+            Options::Bench(opts)
+            | Options::Test(opts)
+            | Options::Benches(opts)
+            | Options::Tests(opts)
+            | Options::Config(opts) => Config::load(&opts.compiler.cargo_args)?,
+            Options::Devices(_) | Options::Clean(_) => Config::default(),
+        };
+        self.into_request_with(&config)
+    }
+
+    fn into_request_with(self, config: &Config) -> Result<Request, Error> {
+        let request = match self {
+            Options::Bench(opts) => Request::Run(from_local_run(Command::Bench, opts, config)?),
+            Options::Test(opts) => Request::Run(from_local_run(Command::Test, opts, config)?),
+            Options::Benches(opts) => Request::Run(from_local_run(Command::Benches, opts, config)?),
+            Options::Tests(opts) => Request::Run(from_local_run(Command::Tests, opts, config)?),
+            Options::Devices(opts) => Request::ListDevices(opts.into()),
+            Options::Clean(opts) => Request::Clean(opts.into()),
+            Options::Config(opts) => {
+                let flags = opts.flags_config();
+                let compilers: Vec<opts::CompilerOptions> = opts.compiler.into();
+                let target = compilers
+                    .into_iter()
+                    .map(|compiler| {
+                        let triple = compiler.target.triple.to_string();
+                        let target_config = flags.clone().or(config.target(&triple));
+                        (triple, target_config)
+                    })
+                    .collect();
+                Request::PrintConfig(Config {
+                    target,
+                    ..Default::default()
+                })
+            }
+        };
+        Ok(request)
     }
 }
 
-fn from_local_run(command: Command, options: LocalRun) -> opts::Options {
-    opts::Options {
-        command,
-        compiler: options.compiler.into(),
-        resources: options.resources.resources,
-        binary: options.binary.into(),
-        android: options.android.into(),
-        ios: options.ios.into(),
-        cli: options.cli.into(),
+impl LocalRun {
+    /// The flags that can also be set in the configuration of the project.
+    fn flags_config(&self) -> TargetConfig {
+        TargetConfig {
+            android_api_lvl: self.android.api_lvl,
+            android_sdk: self.android.sdk.clone(),
+            android_ndk: self.android.ndk.clone(),
+            android_ndk_version: self.android.ndk_version.clone(),
+            ios_mobile_provision: self.ios.mobile_provision.clone(),
+            args: self.binary.args.clone(),
+            resources: self.resources.resources.iter().flatten().cloned().collect(),
+            envs: self.binary.envs.iter().flatten().cloned().collect(),
+        }
     }
 }
 
+/// Returns the options for each requested target.
+fn from_local_run(
+    command: Command,
+    options: LocalRun,
+    config: &Config,
+) -> Result<Vec<opts::Options>, Error> {
+    let flags = options.flags_config();
+    let compilers: Vec<opts::CompilerOptions> = options.compiler.into();
+    let binary = Option::<opts::BinaryOptions>::from(options.binary).unwrap_or_default();
+    let run: opts::RunOptions = options.run.into();
+    let devices: opts::DeviceOptions = options.devices.into();
+    let cli: opts::CliOptions = options.cli.into();
+
+    compilers
+        .into_iter()
+        .map(|compiler| {
+            let target_config = flags.clone().or(config.target(compiler.target.triple));
+
+            let android = target_config.android_options();
+            if compiler.target.os == Some(Os::android) && android.is_none() {
+                bail!(
+                    "{} requires `--android-api-lvl` \
+                    (or `android-api-lvl` in Tai.toml/[package.metadata.tai])",
+                    compiler.target.triple
+                )
+            }
+            let ios = target_config.ios_options();
+            if Platform::of(&compiler.target) == Some(Platform::IosDevice) && ios.is_none() {
+                bail!(
+                    "{} requires `--ios-mobile-provision` \
+                    (or `ios-mobile-provision` in Tai.toml/[package.metadata.tai])",
+                    compiler.target.triple
+                )
+            }
+
+            Ok(opts::Options {
+                command: command.clone(),
+                resources: target_config.resources(),
+                binary: Some(opts::BinaryOptions {
+                    args: target_config.args.clone(),
+                    envs: target_config.envs(),
+                    ..binary.clone()
+                }),
+                run: run.clone(),
+                devices: devices.clone(),
+                android,
+                ios,
+                cli: cli.clone(),
+                compiler,
+            })
+        })
+        .collect()
+}
+
 /// Parse a single key-value pair
 fn parse_key_val<T, U>(
     s: &str,
@@ -88,6 +214,26 @@ where
     Ok((s[..pos].parse()?, s[pos + 1..].parse()?))
 }
 
+/// Parse a duration like `90`, `90s`, `1500ms`, `5m` or `1h`. A number without unit is
+/// interpreted as seconds.
+fn parse_duration(s: &str) -> Result<Duration, anyhow::Error> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| anyhow!("invalid duration: `{}`", s))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(value)),
+        "" | "s" => Ok(Duration::from_secs(value)),
+        "m" => Ok(Duration::from_secs(value * 60)),
+        "h" => Ok(Duration::from_secs(value * 60 * 60)),
+        _ => bail!(
+            "invalid duration: `{}`, expected a unit of ms, s, m or h",
+            s
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
@@ -109,7 +255,7 @@ mod tests {
         };
 
         assert_eq!(
-            &o.compiler.target,
+            &o.compiler.target[0],
             get_builtin_target_by_triple("x86_64-apple-ios").unwrap()
         );
         assert_eq!(
@@ -130,7 +276,7 @@ mod tests {
         };
 
         assert_eq!(
-            &o.compiler.target,
+            &o.compiler.target[0],
             get_builtin_target_by_triple("x86_64-apple-ios").unwrap()
         );
         assert_eq!(
@@ -143,6 +289,116 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tests_with_simulator() {
+        let o = Options::parse_from([
+            "cargo-tai",
+            "tests",
+            "--target",
+            "x86_64-apple-ios",
+            "--simulator",
+            "iPhone 14/iOS 16.2",
+            "--ephemeral-simulator",
+        ]);
+        let o = match o {
+            Options::Tests(o) => o,
+            _ => panic!(""),
+        };
+        let devices: opts::DeviceOptions = o.devices.into();
+        assert_eq!(
+            devices.simulator,
+            Some(opts::SimulatorOptions {
+                spec: Some("iPhone 14/iOS 16.2".to_string()),
+                ephemeral: true,
+                shutdown: false,
+            })
+        );
+
+        let o = Options::parse_from(
+            "cargo-tai tests --target x86_64-apple-ios --boot --shutdown-simulator"
+                .split_whitespace(),
+        );
+        let o = match o {
+            Options::Tests(o) => o,
+            _ => panic!(""),
+        };
+        let devices: opts::DeviceOptions = o.devices.into();
+        assert_eq!(
+            devices.simulator,
+            Some(opts::SimulatorOptions {
+                spec: None,
+                ephemeral: false,
+                shutdown: true,
+            })
+        );
+
+        assert!(Options::try_parse_from(
+            "cargo-tai tests --target x86_64-apple-ios --ephemeral-simulator".split_whitespace()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_tests_with_avd() {
+        let o = Options::parse_from(
+            "cargo-tai tests --target x86_64-linux-android --avd Pixel_6_API_33 --headless"
+                .split_whitespace(),
+        );
+        let o = match o {
+            Options::Tests(o) => o,
+            _ => panic!(""),
+        };
+        let devices: opts::DeviceOptions = o.devices.into();
+        assert_eq!(
+            devices.emulator,
+            Some(opts::EmulatorOptions {
+                avd: "Pixel_6_API_33".to_string(),
+                headless: true,
+            })
+        );
+
+        assert!(Options::try_parse_from(
+            "cargo-tai tests --target x86_64-linux-android --headless".split_whitespace()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_tests_with_filters() {
+        let o = Options::parse_from([
+            "cargo-tai",
+            "tests",
+            "--target",
+            "aarch64-linux-android",
+            "--filter",
+            "tests::read file",
+            "--exact",
+            "--ignored",
+            "--skip",
+            "slow",
+            "--skip",
+            "-x",
+        ]);
+        let o = match o {
+            Options::Tests(o) => o,
+            _ => panic!(""),
+        };
+
+        let binary: Option<opts::BinaryOptions> = o.binary.into();
+        assert_eq!(
+            binary.unwrap().binary_args(),
+            vec![
+                "--exact",
+                "--ignored",
+                "--skip",
+                "slow",
+                "--skip",
+                "-x",
+                "tests::read file"
+            ]
+        );
+    }
+
     #[test]
     fn test_tests_with_cargo_arguments() {
         let o = Options::parse_from(
@@ -155,7 +411,7 @@ mod tests {
         };
 
         assert_eq!(
-            &o.compiler.target,
+            &o.compiler.target[0],
             get_builtin_target_by_triple("x86_64-apple-ios").unwrap()
         );
         assert_eq!(
@@ -177,7 +433,7 @@ mod tests {
         };
 
         assert_eq!(
-            &o.compiler.target,
+            &o.compiler.target[0],
             get_builtin_target_by_triple("x86_64-apple-ios").unwrap()
         );
         assert_eq!(
@@ -188,9 +444,9 @@ mod tests {
     }
 
     #[test]
-    fn test_test_with_cargo_ndk_arguments_and_cargo_arguments() {
+    fn test_test_with_android_and_cargo_arguments() {
         let o = Options::parse_from(
-            "cargo-tai test --target x86_64-linux-android --android-api-lvl 21 --android-ndk path --cargo-ndk-args --no-strip,--bindgen --args test_x86_64_android -- integration"
+            "cargo-tai test --target x86_64-linux-android --android-api-lvl 21 --android-ndk path --args test_x86_64_android -- integration"
                 .split_whitespace(),
         );
         let o = match o {
@@ -199,20 +455,367 @@ mod tests {
         };
 
         assert_eq!(
-            &o.compiler.target,
+            &o.compiler.target[0],
             get_builtin_target_by_triple("x86_64-linux-android").unwrap()
         );
         assert_eq!(o.android.api_lvl.unwrap(), 21);
         assert_eq!(o.android.ndk.unwrap(), PathBuf::from("path"));
         assert!(o.android.sdk.is_none());
-        assert_eq!(
-            &o.android.cargo_ndk_args.unwrap(),
-            &vec!["--no-strip".to_string(), "--bindgen".to_string()]
-        );
         assert_eq!(
             &o.binary.args.unwrap(),
             &vec!["test_x86_64_android".to_string()]
         );
         assert_eq!(&o.compiler.cargo_args, &vec!["integration".to_string(),]);
     }
+
+    #[test]
+    fn test_tests_with_jobs() {
+        let o = Options::parse_from(
+            "cargo-tai tests --target aarch64-linux-android --android-api-lvl 21 --android-ndk path --jobs 3"
+                .split_whitespace(),
+        );
+        let o = match o {
+            Options::Tests(o) => o,
+            _ => panic!(""),
+        };
+
+        let run: opts::RunOptions = o.run.into();
+        assert_eq!(run.jobs, 3);
+    }
+
+    #[test]
+    fn test_tests_with_parallel_devices() {
+        let o = Options::parse_from(
+            "cargo-tai tests --target aarch64-linux-android --android-api-lvl 21 --android-ndk path --parallel-devices"
+                .split_whitespace(),
+        );
+        let o = match o {
+            Options::Tests(o) => o,
+            _ => panic!(""),
+        };
+
+        let run: opts::RunOptions = o.run.into();
+        assert_eq!(run.jobs, usize::MAX);
+    }
+
+    #[test]
+    fn test_tests_with_zero_jobs() {
+        let o = Options::try_parse_from(
+            "cargo-tai tests --target aarch64-linux-android --android-api-lvl 21 --android-ndk path --jobs 0"
+                .split_whitespace(),
+        );
+        assert!(o.is_err());
+    }
+
+    #[test]
+    fn test_tests_with_json_report() {
+        let o = Options::parse_from(
+            "cargo-tai tests --target aarch64-linux-android --android-api-lvl 21 --android-ndk path --message-format json --report report.json"
+                .split_whitespace(),
+        );
+        let o = match o {
+            Options::Tests(o) => o,
+            _ => panic!(""),
+        };
+
+        let run: opts::RunOptions = o.run.into();
+        assert_eq!(run.message_format, opts::MessageFormat::Json);
+        assert_eq!(run.report.unwrap(), PathBuf::from("report.json"));
+    }
+
+    #[test]
+    fn test_tests_with_timeouts() {
+        let o = Options::parse_from(
+            "cargo-tai tests --target aarch64-linux-android --android-api-lvl 21 --android-ndk path --timeout 90 --device-timeout 5m"
+                .split_whitespace(),
+        );
+        let o = match o {
+            Options::Tests(o) => o,
+            _ => panic!(""),
+        };
+
+        let binary: Option<opts::BinaryOptions> = o.binary.into();
+        assert_eq!(binary.unwrap().timeout, Some(Duration::from_secs(90)));
+        let run: opts::RunOptions = o.run.into();
+        assert_eq!(run.device_timeout, Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn test_tests_with_artifacts() {
+        let o = Options::parse_from(
+            "cargo-tai benches --target x86_64-apple-ios --artifacts".split_whitespace(),
+        );
+        let o = match o {
+            Options::Benches(o) => o,
+            _ => panic!(""),
+        };
+        assert_eq!(
+            o.run.artifacts,
+            Some(PathBuf::from(opts::DEFAULT_ARTIFACTS_DIR))
+        );
+
+        let o = Options::parse_from(
+            "cargo-tai tests --target x86_64-apple-ios --artifacts target/reports"
+                .split_whitespace(),
+        );
+        let o = match o {
+            Options::Tests(o) => o,
+            _ => panic!(""),
+        };
+        assert_eq!(o.run.artifacts, Some(PathBuf::from("target/reports")));
+    }
+
+    #[test]
+    fn test_benches_with_baseline() {
+        let o = Options::parse_from(
+            "cargo-tai benches --target aarch64-linux-android --save-baseline new --baseline main \
+            --regression-threshold 10"
+                .split_whitespace(),
+        );
+        let o = match o {
+            Options::Benches(o) => o,
+            _ => panic!(""),
+        };
+        let run: opts::RunOptions = o.run.into();
+        assert_eq!(
+            run.baseline,
+            Some(opts::BaselineOptions {
+                save: Some("new".to_string()),
+                compare: Some("main".to_string()),
+                threshold: 10.0,
+            })
+        );
+        assert_eq!(
+            run.artifacts,
+            Some(PathBuf::from(opts::DEFAULT_ARTIFACTS_DIR))
+        );
+
+        let o = Options::parse_from(
+            "cargo-tai benches --target aarch64-linux-android --baseline main".split_whitespace(),
+        );
+        let o = match o {
+            Options::Benches(o) => o,
+            _ => panic!(""),
+        };
+        let run: opts::RunOptions = o.run.into();
+        assert_eq!(
+            run.baseline.unwrap().threshold,
+            opts::DEFAULT_REGRESSION_THRESHOLD
+        );
+
+        let o = Options::parse_from(
+            "cargo-tai benches --target aarch64-linux-android".split_whitespace(),
+        );
+        let o = match o {
+            Options::Benches(o) => o,
+            _ => panic!(""),
+        };
+        let run: opts::RunOptions = o.run.into();
+        assert_eq!(run.baseline, None);
+        assert_eq!(run.artifacts, None);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(
+            parse_duration("1500ms").unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert!(parse_duration("5 min").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+
+    #[test]
+    fn test_tests_with_devices() {
+        let o = Options::parse_from(
+            [
+                "cargo-tai",
+                "tests",
+                "--target",
+                "aarch64-linux-android",
+                "--android-api-lvl",
+                "21",
+                "--android-ndk",
+                "path",
+                "--device",
+                "Pixel 6",
+                "--device",
+                "api=33",
+                "--first-device",
+            ]
+            .iter(),
+        );
+        let o = match o {
+            Options::Tests(o) => o,
+            _ => panic!(""),
+        };
+
+        let devices: opts::DeviceOptions = o.devices.into();
+        assert_eq!(devices.patterns, vec!["Pixel 6", "api=33"]);
+        assert!(devices.first);
+    }
+
+    #[test]
+    fn test_tests_with_all_and_first_device() {
+        let o = Options::try_parse_from(
+            "cargo-tai tests --target aarch64-linux-android --android-api-lvl 21 --android-ndk path --all-devices --first-device"
+                .split_whitespace(),
+        );
+        assert!(o.is_err());
+    }
+
+    #[test]
+    fn test_devices() {
+        let o = Options::parse_from("cargo-tai devices --json".split_whitespace());
+
+        match o.into_request_with(&Config::default()).unwrap() {
+            Request::ListDevices(opts::ListDevicesOptions { json, .. }) => assert!(json),
+            _ => panic!("expected a devices request"),
+        }
+    }
+
+    #[test]
+    fn test_clean() {
+        let o = Options::parse_from(
+            "cargo-tai clean --device -- --manifest-path ./Cargo.toml".split_whitespace(),
+        );
+
+        match o.into_request_with(&Config::default()).unwrap() {
+            Request::Clean(opts::CleanOptions {
+                device, cargo_args, ..
+            }) => {
+                assert!(device);
+                assert_eq!(cargo_args, vec!["--manifest-path", "./Cargo.toml"]);
+            }
+            _ => panic!("expected a clean request"),
+        }
+    }
+
+    #[test]
+    fn test_multiple_targets() {
+        let o = Options::parse_from(
+            "cargo-tai tests --target x86_64-apple-ios --target aarch64-linux-android,i686-linux-android --android-api-lvl 21 --android-ndk ~/ndk"
+                .split_whitespace(),
+        );
+
+        let targets = match o.into_request_with(&Config::default()).unwrap() {
+            Request::Run(opts) => opts
+                .into_iter()
+                .map(|opts| opts.compiler.target.triple)
+                .collect::<Vec<_>>(),
+            _ => panic!("expected a run request"),
+        };
+        assert_eq!(
+            targets,
+            vec![
+                "x86_64-apple-ios",
+                "aarch64-linux-android",
+                "i686-linux-android"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_all_android_targets() {
+        let o = Options::parse_from(
+            "cargo-tai tests --all-android-targets --android-api-lvl 21 --android-ndk ~/ndk"
+                .split_whitespace(),
+        );
+        match o.into_request_with(&Config::default()).unwrap() {
+            Request::Run(opts) => assert_eq!(opts.len(), 4),
+            _ => panic!("expected a run request"),
+        }
+
+        let missing_api_lvl = Options::parse_from(
+            "cargo-tai tests --all-android-targets --android-ndk ~/ndk".split_whitespace(),
+        );
+        assert!(missing_api_lvl
+            .into_request_with(&Config::default())
+            .is_err());
+
+        let both = Options::try_parse_from(
+            "cargo-tai tests --all-android-targets --target x86_64-apple-ios --android-api-lvl 21 --android-ndk ~/ndk"
+                .split_whitespace(),
+        );
+        assert!(both.is_err());
+    }
+
+    #[test]
+    fn test_tests_with_ndk_version() {
+        let o = Options::parse_from(
+            "cargo-tai tests --target aarch64-linux-android --android-api-lvl 21 --ndk-version 25"
+                .split_whitespace(),
+        );
+        let android = match o.into_request_with(&Config::default()).unwrap() {
+            Request::Run(mut opts) => opts.remove(0).android.unwrap(),
+            _ => panic!("expected a run request"),
+        };
+        assert_eq!(android.api_lvl, 21);
+        assert_eq!(android.ndk_version.as_deref(), Some("25"));
+    }
+
+    #[test]
+    fn test_flags_take_precedence_over_config() {
+        let config: Config = toml::from_str(
+            r#"
+android-api-lvl = 21
+android-ndk = "/opt/ndk"
+envs = { RUST_LOG = "info" }
+
+[target.aarch64-linux-android]
+android-api-lvl = 24
+"#,
+        )
+        .unwrap();
+        let o = Options::parse_from(
+            "cargo-tai tests --target aarch64-linux-android --target x86_64-linux-android --android-api-lvl 28 -e RUST_BACKTRACE=1"
+                .split_whitespace(),
+        );
+
+        let opts = match o.into_request_with(&config).unwrap() {
+            Request::Run(opts) => opts,
+            _ => panic!("expected a run request"),
+        };
+        let android = opts[0].android.as_ref().unwrap();
+        assert_eq!(android.api_lvl, 28);
+        assert_eq!(android.ndk, Some(PathBuf::from("/opt/ndk")));
+        assert_eq!(
+            opts[1].binary.as_ref().unwrap().envs,
+            Some(vec![
+                ("RUST_BACKTRACE".to_string(), "1".to_string()),
+                ("RUST_LOG".to_string(), "info".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_config_uses_target_sections() {
+        let config: Config = toml::from_str(
+            r#"
+android-api-lvl = 21
+
+[target.aarch64-linux-android]
+android-api-lvl = 24
+"#,
+        )
+        .unwrap();
+        let o = Options::parse_from(
+            "cargo-tai config --target aarch64-linux-android --target x86_64-linux-android"
+                .split_whitespace(),
+        );
+
+        let config = match o.into_request_with(&config).unwrap() {
+            Request::PrintConfig(config) => config,
+            _ => panic!("expected a config request"),
+        };
+        assert_eq!(
+            config.target["aarch64-linux-android"].android_api_lvl,
+            Some(24)
+        );
+        assert_eq!(
+            config.target["x86_64-linux-android"].android_api_lvl,
+            Some(21)
+        );
+    }
 }
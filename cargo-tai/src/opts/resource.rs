@@ -6,11 +6,15 @@ use super::parse_key_val;
 
 #[derive(Parser, Debug)]
 pub struct ResourceOptions {
-    /// Resources to include in the app. Format: `id=local_path`
+    /// Resources to include in the app. Format: `[binary:]id=local_path`
+    ///
+    /// `local_path` can be a file, a directory or a glob pattern. Directories are copied
+    /// recursively, the files that match a pattern are copied into the directory `id`.
+    /// With `binary`, the resource is only included in the bundle of that test/bench target.
     ///
     /// Example:
     ///
-    /// `cargo-tai test -r test_txt=./data/text.txt`
+    /// `cargo-tai test -r test_txt=./data/text.txt -r integration:model=./data/model -r "fixtures=./data/*.json"`
     #[clap(short, long, parse(try_from_str = parse_key_val))]
     pub resources: Option<Vec<(String, PathBuf)>>,
 }
@@ -0,0 +1,181 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{bail, Error};
+use clap::Parser;
+use tai_lib::common::opts;
+
+use super::parse_duration;
+
+#[derive(Parser, Debug)]
+pub struct RunOptions {
+    /// Number of devices on which the bundles are run in parallel.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai test --jobs 4`
+    #[clap(short, long, parse(try_from_str = parse_jobs))]
+    pub jobs: Option<usize>,
+
+    /// Run the bundles on all devices in parallel.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai test --parallel-devices`
+    #[clap(long, conflicts_with = "jobs")]
+    pub parallel_devices: bool,
+
+    /// Run all bundles regardless of failure and print a summary at the end.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --no-fail-fast`
+    #[clap(long)]
+    pub no_fail_fast: bool,
+
+    /// Time limit for all binaries on a device. Binaries that have not finished
+    /// before the limit are reported as timed out. Format: `<number>[ms|s|m|h]`
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --device-timeout 30m`
+    #[clap(long, parse(try_from_str = parse_duration))]
+    pub device_timeout: Option<Duration>,
+
+    /// The output format of the run. `json` prints one JSON event per line to stdout
+    /// and moves the output of the devices to stderr.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --message-format json`
+    #[clap(
+        long,
+        default_value = "human",
+        possible_values = &["human", "json"],
+        parse(try_from_str = parse_message_format)
+    )]
+    pub message_format: opts::MessageFormat,
+
+    /// Write the JSON events of the run to a file.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --report target/cargo-tai/report.json`
+    #[clap(long)]
+    pub report: Option<PathBuf>,
+
+    /// Write the test results as JUnit XML files into a directory,
+    /// one file per device and test binary.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai tests --junit target/cargo-tai/junit`
+    #[clap(long)]
+    pub junit: Option<PathBuf>,
+
+    /// Copy a directory of each bundle back from the device after the run into
+    /// `target/cargo-tai/artifacts/<device>/<bundle>`. The directory is relative to the working
+    /// directory of the binary on Android and to the `Documents` directory of the app on the
+    /// iOS simulator. Without a directory, the one of `tai_util::dirs::artifacts_dir()` is copied,
+    /// which also contains the reports of `tai_util::criterion`.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai benches --artifacts` or `cargo-tai tests --artifacts target/reports`
+    #[clap(
+        long,
+        value_name = "REMOTE-SUBDIR",
+        min_values = 0,
+        default_missing_value = opts::DEFAULT_ARTIFACTS_DIR
+    )]
+    pub artifacts: Option<PathBuf>,
+
+    /// Save the criterion estimates of the benchmarks as a baseline in
+    /// `target/cargo-tai/baselines/<device>/<name>`. Implies `--artifacts` and needs the
+    /// benches to use `tai_util::criterion_main_tai!`.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai benches --save-baseline main`
+    #[clap(long, value_name = "NAME")]
+    pub save_baseline: Option<String>,
+
+    /// Compare the criterion estimates of the benchmarks with a saved baseline, print the
+    /// changes and fail if a benchmark regressed by more than `--regression-threshold`.
+    /// Implies `--artifacts`.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai benches --baseline main`
+    #[clap(long, value_name = "NAME")]
+    pub baseline: Option<String>,
+
+    /// The percentage by which a benchmark may be slower than the baseline (`--baseline`),
+    /// 5 by default.
+    ///
+    /// Example:
+    ///
+    /// `cargo-tai benches --baseline main --regression-threshold 10`
+    #[clap(long, value_name = "PERCENT", requires = "baseline")]
+    pub regression_threshold: Option<f64>,
+}
+
+fn parse_jobs(src: &str) -> Result<usize, Error> {
+    match src.parse()? {
+        0 => bail!("the number of jobs must be greater than 0"),
+        jobs => Ok(jobs),
+    }
+}
+
+fn parse_message_format(src: &str) -> Result<opts::MessageFormat, Error> {
+    match src {
+        "human" => Ok(opts::MessageFormat::Human),
+        "json" => Ok(opts::MessageFormat::Json),
+        _ => bail!("unknown message format: {}", src),
+    }
+}
+
+impl From<RunOptions> for opts::RunOptions {
+    fn from(
+        RunOptions {
+            jobs,
+            parallel_devices,
+            no_fail_fast,
+            device_timeout,
+            message_format,
+            report,
+            junit,
+            artifacts,
+            save_baseline,
+            baseline,
+            regression_threshold,
+        }: RunOptions,
+    ) -> Self {
+        let jobs = match (jobs, parallel_devices) {
+            (_, true) => usize::MAX,
+            (Some(jobs), false) => jobs,
+            (None, false) => 1,
+        };
+        let baseline = match (save_baseline, baseline) {
+            (None, None) => None,
+            (save, compare) => Some(opts::BaselineOptions {
+                save,
+                compare,
+                threshold: regression_threshold.unwrap_or(opts::DEFAULT_REGRESSION_THRESHOLD),
+            }),
+        };
+        let artifacts = match (artifacts, &baseline) {
+            (None, Some(_)) => Some(PathBuf::from(opts::DEFAULT_ARTIFACTS_DIR)),
+            (artifacts, _) => artifacts,
+        };
+        opts::RunOptions {
+            jobs,
+            no_fail_fast,
+            device_timeout,
+            message_format,
+            report,
+            junit,
+            artifacts,
+            baseline,
+        }
+    }
+}
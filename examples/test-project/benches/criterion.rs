@@ -13,31 +13,4 @@ fn criterion_benchmark(c: &mut Criterion) {
 }
 
 criterion_group!(benches, criterion_benchmark);
-
-fn main() {
-    #[cfg(target_os = "ios")]
-    {
-        use tai_util::ios::user_documents;
-        let path = user_documents().join("target");
-        std::fs::create_dir(&path);
-        std::env::set_var("CRITERION_HOME", path);
-    }
-
-    #[cfg(target_os = "android")]
-    {
-        let path = std::env::current_exe().expect("current exe path not accessible");
-        let path = path
-            .parent()
-            .map(|p| p.parent())
-            .flatten()
-            .expect("parent folder does not exist");
-        std::fs::create_dir(&path);
-        std::env::set_var("CRITERION_HOME", path);
-    }
-
-    benches();
-
-    criterion::Criterion::default()
-        .configure_from_args()
-        .final_summary();
-}
+tai_util::criterion_main_tai!(benches);
@@ -3,7 +3,10 @@ use std::{env, path::PathBuf};
 fn sdk_path(target: &str) -> Result<String, std::io::Error> {
     use std::process::Command;
 
-    let sdk = if target == "x86_64-apple-ios" || target == "i386-apple-ios" {
+    let sdk = if target == "x86_64-apple-ios"
+        || target == "i386-apple-ios"
+        || target == "aarch64-apple-ios-sim"
+    {
         "iphonesimulator"
     } else if target == "aarch64-apple-ios"
         || target == "armv7-apple-ios"
@@ -39,6 +42,8 @@ fn build(sdk_path: Option<&str>, target: &str) {
     // -arch arm64 but it looks cleaner to just change the target.
     let target = if target == "aarch64-apple-ios" {
         "arm64-apple-ios"
+    } else if target == "aarch64-apple-ios-sim" {
+        "arm64-apple-ios-simulator"
     } else {
         target
     };
@@ -1,6 +1,6 @@
 use std::{
     fs::{copy, create_dir_all, remove_dir_all},
-    path::{Path, PathBuf},
+    path::Path,
 };
 
 use tracing::{debug, instrument};
@@ -9,6 +9,7 @@ use crate::{
     common::{
         bundle::{copy_resources, BuiltBundle},
         compiler::BuiltUnit,
+        opts::Resource,
     },
     TaiResult,
 };
@@ -17,7 +18,7 @@ use crate::{
 pub fn create_bundle<P: AsRef<Path>>(
     unit: BuiltUnit,
     bundles_root: P,
-    resources: &Option<Vec<(String, PathBuf)>>,
+    resources: &Option<Vec<Resource>>,
 ) -> TaiResult<BuiltBundle> {
     let bundle_root = bundles_root
         .as_ref()
@@ -35,7 +36,7 @@ pub fn create_bundle<P: AsRef<Path>>(
     debug!("copy {} to {}", &unit.artifact.display(), to.display());
 
     if let Some(resources) = resources {
-        copy_resources(&bundle_root, resources)?;
+        copy_resources(&bundle_root, &unit.target_name, resources)?;
     }
 
     Ok(BuiltBundle {
@@ -1,57 +1,79 @@
-use std::process::Command;
+use std::{path::Path, process::Command};
 
+use anyhow::anyhow;
 use tracing::debug;
 
-use crate::{
-    common::opts::{AndroidOptions, Options},
-    TaiResult,
-};
+use crate::{common::opts::Options, TaiResult};
 
-use super::tools::AndroidEnv;
+use super::tools::{ndk, AndroidEnv};
 
-pub fn benches_command(env: &AndroidEnv, requested: &Options) -> TaiResult<Command> {
-    let mut cmd = setup_cargo_ndk(env, requested)?;
+/// The directory in the cargo-tai target directory with the linker wrappers of the ndk.
+const LINKER_DIR: &str = "ndk-linker";
+
+pub fn benches_command(
+    env: &AndroidEnv,
+    requested: &Options,
+    tai_target: &Path,
+) -> TaiResult<Command> {
+    let mut cmd = setup_cargo(env, requested, tai_target)?;
     cmd.args(["--benches"]);
     Ok(cmd)
 }
 
-pub fn tests_command(env: &AndroidEnv, requested: &Options) -> TaiResult<Command> {
-    let mut cmd = setup_cargo_ndk(env, requested)?;
+pub fn tests_command(
+    env: &AndroidEnv,
+    requested: &Options,
+    tai_target: &Path,
+) -> TaiResult<Command> {
+    let mut cmd = setup_cargo(env, requested, tai_target)?;
     cmd.args(["--tests"]);
     Ok(cmd)
 }
 
-pub fn bench_command(env: &AndroidEnv, requested: &Options) -> TaiResult<Command> {
-    let mut cmd = setup_cargo_ndk(env, requested)?;
+pub fn bench_command(
+    env: &AndroidEnv,
+    requested: &Options,
+    tai_target: &Path,
+) -> TaiResult<Command> {
+    let mut cmd = setup_cargo(env, requested, tai_target)?;
     cmd.args(["--bench"]);
     Ok(cmd)
 }
 
-pub fn test_command(env: &AndroidEnv, requested: &Options) -> TaiResult<Command> {
-    let mut cmd = setup_cargo_ndk(env, requested)?;
+pub fn test_command(
+    env: &AndroidEnv,
+    requested: &Options,
+    tai_target: &Path,
+) -> TaiResult<Command> {
+    let mut cmd = setup_cargo(env, requested, tai_target)?;
     cmd.args(["--test"]);
     Ok(cmd)
 }
 
-fn setup_cargo_ndk(env: &AndroidEnv, requested: &Options) -> TaiResult<Command> {
-    const NDK_HOME_KEY: &str = "ANDROID_NDK_HOME";
-    const SDK_HOME_KEY: &str = "ANDROID_SDK_HOME";
-
-    debug!("{}={}", NDK_HOME_KEY, env.ndk.display());
-    debug!("{}={}", SDK_HOME_KEY, env.sdk.display());
+/// A `cargo build` that uses the toolchain of the ndk for the target and api level.
+fn setup_cargo(env: &AndroidEnv, requested: &Options, tai_target: &Path) -> TaiResult<Command> {
+    let api_lvl = requested
+        .android
+        .as_ref()
+        .map(|android| android.api_lvl)
+        .ok_or_else(|| anyhow!("no android api level"))?;
+    let toolchain = ndk::toolchain_env(
+        &env.ndk,
+        ndk::HOST_TAG,
+        &requested.compiler.target,
+        api_lvl,
+        &tai_target.join(LINKER_DIR),
+    )?;
+    if let Some(wrapper) = &toolchain.linker_wrapper {
+        wrapper.write()?;
+    }
 
     let mut cmd = Command::new("cargo");
-    cmd.env(NDK_HOME_KEY, &env.ndk)
-        .env(SDK_HOME_KEY, &env.sdk)
-        .args(["ndk", "-t", requested.compiler.target.triple]);
-    if let Some(AndroidOptions {
-        cargo_ndk_args: Some(args),
-        ..
-    }) = requested.android.as_ref()
-    {
-        cmd.args(args);
+    for (key, value) in toolchain.env {
+        debug!("{}={}", key, value.to_string_lossy());
+        cmd.env(key, value);
     }
-    cmd.arg("build");
+    cmd.args(["build", "--target", requested.compiler.target.triple]);
 
     Ok(cmd)
 }
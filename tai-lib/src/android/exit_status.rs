@@ -0,0 +1,112 @@
+use crate::common::report::RunStatus;
+
+/// The start script prints this sentinel followed by the exit status of the test binary,
+/// because older versions of `adb shell` do not propagate the exit status.
+pub const EXIT_STATUS_SENTINEL: &str = "cargo-tai-exit-status:";
+
+/// The exit status of a test binary as reported by the shell on the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteExitStatus {
+    /// The binary exited with this code.
+    Code(i32),
+    /// The binary was killed by this signal.
+    Signal(i32),
+    /// The binary does not exist (shell status 127).
+    NotFound,
+    /// The binary could not be executed (shell status 126).
+    NotExecutable,
+}
+
+impl From<i32> for RemoteExitStatus {
+    fn from(status: i32) -> Self {
+        match status {
+            126 => Self::NotExecutable,
+            127 => Self::NotFound,
+            // the shell reports a process that was killed by signal N as 128 + N
+            129..=192 => Self::Signal(status - 128),
+            code => Self::Code(code),
+        }
+    }
+}
+
+impl From<RemoteExitStatus> for RunStatus {
+    fn from(status: RemoteExitStatus) -> Self {
+        match status {
+            RemoteExitStatus::Code(code) => RunStatus::from_exit_code(Some(code)),
+            RemoteExitStatus::Signal(signal) => RunStatus::Crashed { signal },
+            RemoteExitStatus::NotFound => RunStatus::Error {
+                message: "test binary not found on the device".to_string(),
+            },
+            RemoteExitStatus::NotExecutable => RunStatus::Error {
+                message: "test binary is not executable on the device".to_string(),
+            },
+        }
+    }
+}
+
+/// Extracts the exit status that the start script printed after the sentinel.
+///
+/// Returns `None` if the output does not contain the sentinel, e.g. because the
+/// connection to the device was lost before the binary exited.
+pub fn parse_exit_status(stdout: &[u8]) -> Option<RemoteExitStatus> {
+    let stdout = String::from_utf8_lossy(stdout);
+    let (_, status) = stdout.rsplit_once(EXIT_STATUS_SENTINEL)?;
+    status
+        .lines()
+        .next()?
+        .trim()
+        .parse::<i32>()
+        .ok()
+        .map(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_exit_status() {
+        let status = |stdout: &str| parse_exit_status(stdout.as_bytes());
+
+        assert_eq!(
+            status("test result: ok.\ncargo-tai-exit-status:0\n"),
+            Some(RemoteExitStatus::Code(0))
+        );
+        assert_eq!(
+            status("test result: FAILED.\r\ncargo-tai-exit-status:101\r\n"),
+            Some(RemoteExitStatus::Code(101))
+        );
+        assert_eq!(
+            status("running 1 test\ncargo-tai-exit-status:139\n"),
+            Some(RemoteExitStatus::Signal(11))
+        );
+        assert_eq!(
+            status("no newline at the endcargo-tai-exit-status:127"),
+            Some(RemoteExitStatus::NotFound)
+        );
+        assert_eq!(
+            status("cargo-tai-exit-status:126\n"),
+            Some(RemoteExitStatus::NotExecutable)
+        );
+        assert_eq!(status("running 1 test\n"), None);
+        assert_eq!(status("cargo-tai-exit-status:\n"), None);
+    }
+
+    #[test]
+    fn test_into_run_status() {
+        assert_eq!(
+            RunStatus::from(RemoteExitStatus::Code(0)),
+            RunStatus::Passed
+        );
+        assert_eq!(
+            RunStatus::from(RemoteExitStatus::Code(101)),
+            RunStatus::Failed {
+                exit_code: Some(101)
+            }
+        );
+        assert_eq!(
+            RunStatus::from(RemoteExitStatus::Signal(6)),
+            RunStatus::Crashed { signal: 6 }
+        );
+    }
+}
@@ -1,5 +1,10 @@
 mod bundle;
 mod compiler;
+mod exit_status;
 pub mod platform;
+mod resource_cache;
 pub mod task;
 mod tools;
+
+/// The directory on the device in which the bundles are installed.
+const ANDROID_REMOTE_WORKDIR: &str = "/data/local/tmp/cargo-tai";
@@ -1,28 +1,106 @@
+use std::{env, path::Path};
+
+use cfg_expr::targets::Arch;
+
 use crate::{
+    android::{
+        resource_cache,
+        tools::{adb, emulator::Emulator, AndroidEnv},
+    },
     common::{
-        opts::Options,
-        task::{
-            context::Context, get_project_metadata::GetProjectMetadata, set_bench_arg::SetBenchArg,
-            Runner,
-        },
+        devices::ReachableDevice,
+        opts::{CleanOptions, ListDevicesOptions, Options},
+        report::RunReport,
+        task::{context::Context, set_bench_arg::SetBenchArg, Runner},
     },
     TaiResult,
 };
 
-use super::task::{BuildBuiltUnits, CreateBundles, GetAndroidEnv, ListDevices, RunOnDevices, Task};
+use super::task::{
+    BuildBuiltUnits, CreateBundles, GetAndroidEnv, ListDevices, RequestedEmulator, RunOnDevices,
+    Task,
+};
 
-pub fn run_command(requested: Options) -> TaiResult<()> {
-    Runner::execute(
+pub fn run_command(context: Context) -> TaiResult<RunReport> {
+    let mut context = Runner::execute(&[Task::GetAndroidEnv(GetAndroidEnv)], context)?;
+    let opts = context.get::<Options>();
+    let mut emulator = match &opts.devices.emulator {
+        Some(emulator) => Some(Emulator::start(
+            context.get(),
+            emulator,
+            &opts.compiler.target.arch,
+        )?),
+        None => None,
+    };
+    let env = context.get::<AndroidEnv>().clone();
+    context.insert(RequestedEmulator(
+        emulator.as_ref().map(|emulator| emulator.serial.clone()),
+    ));
+
+    let result = Runner::execute(
         &[
-            Task::GetAndroidEnv(GetAndroidEnv),
-            Task::GetProjectMetadata(GetProjectMetadata),
+            Task::ListDevices(ListDevices),
             Task::SetBenchArg(SetBenchArg),
             Task::BuildBuiltUnits(BuildBuiltUnits),
             Task::CreateBundles(CreateBundles),
-            Task::ListDevices(ListDevices),
             Task::RunOnDevices(RunOnDevices),
         ],
-        Context::from(requested),
-    )?;
-    Ok(())
+        context,
+    );
+
+    if let Some(emulator) = emulator.as_mut() {
+        emulator.kill(&env);
+    }
+    Ok(result?.remove())
+}
+
+pub fn list_devices(requested: &ListDevicesOptions) -> TaiResult<Vec<ReachableDevice>> {
+    let env = AndroidEnv::adb_only(
+        requested.android_sdk.clone(),
+        requested.android_ndk.clone(),
+        &env::current_dir()?,
+    );
+    let devices = adb::devices(&env)?
+        .into_iter()
+        .map(|device| ReachableDevice {
+            platform: "android",
+            os_version: match (device.os_version, device.api_level) {
+                (Some(version), Some(api_level)) => {
+                    Some(format!("{} (API {})", version, api_level))
+                }
+                (version, _) => version,
+            },
+            targets: device.archs.into_iter().filter_map(target_triple).collect(),
+            id: device.id,
+            name: device.model,
+            arch: device.abi,
+        })
+        .collect();
+    Ok(devices)
+}
+
+/// Removes the resource cache from all devices and returns their ids.
+pub fn clean_devices(requested: &CleanOptions, project_dir: &Path) -> TaiResult<Vec<String>> {
+    let env = AndroidEnv::adb_only(
+        requested.android_sdk.clone(),
+        requested.android_ndk.clone(),
+        project_dir,
+    );
+    adb::devices(&env)?
+        .into_iter()
+        .map(|device| {
+            resource_cache::clean(&env, &device.id)?;
+            Ok(device.id)
+        })
+        .collect()
+}
+
+fn target_triple(arch: Arch) -> Option<&'static str> {
+    match arch {
+        Arch::aarch64 => Some("aarch64-linux-android"),
+        Arch::arm => Some("armv7-linux-androideabi"),
+        Arch::x86 => Some("i686-linux-android"),
+        Arch::x86_64 => Some("x86_64-linux-android"),
+        _ => None,
+    }
 }
@@ -0,0 +1,252 @@
+//! A cache for the resources of the bundles on the device.
+//!
+//! Every file of the data directory of a bundle is stored once under the SHA-256 hash of its
+//! content in `<ANDROID_REMOTE_WORKDIR>/cache`. The data directory of an installed bundle only
+//! contains symbolic links into the cache, so only the files whose hash is missing are pushed.
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+use tracing::{debug, instrument};
+use walkdir::WalkDir;
+
+use crate::TaiResult;
+
+use super::{
+    tools::{adb, AndroidEnv},
+    ANDROID_REMOTE_WORKDIR,
+};
+
+const CACHE_DIR_NAME: &str = "cache";
+/// `adb shell` commands are split so that they do not exceed this length.
+const MAX_SCRIPT_LEN: usize = 4096;
+
+/// The directory of the cache on the device.
+pub fn cache_dir() -> PathBuf {
+    Path::new(ANDROID_REMOTE_WORKDIR).join(CACHE_DIR_NAME)
+}
+
+/// A file of the data directory of a bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedFile {
+    /// The path relative to the bundle root, e.g. `test-data/model/config.toml`
+    pub path: PathBuf,
+    /// The SHA-256 hash of the content
+    pub hash: String,
+}
+
+/// Hashes the files of the data directory of the bundle at `bundle_root`.
+pub fn hash_resources(bundle_root: &Path) -> TaiResult<Vec<CachedFile>> {
+    let data_dir = bundle_root.join(tai_util::DATA_DIR_NAME);
+    if !data_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut files = Vec::new();
+    for entry in WalkDir::new(&data_dir).sort_by_file_name() {
+        let entry = entry.with_context(|| format!("Failed to read {}", data_dir.display()))?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let mut file = File::open(entry.path())
+            .with_context(|| format!("Failed to open {}", entry.path().display()))?;
+        let mut hasher = Sha256::new();
+        io::copy(&mut file, &mut hasher)
+            .with_context(|| format!("Failed to hash {}", entry.path().display()))?;
+        files.push(CachedFile {
+            path: entry.path().strip_prefix(bundle_root)?.to_path_buf(),
+            hash: format!("{:x}", hasher.finalize()),
+        });
+    }
+    Ok(files)
+}
+
+/// Pushes the files of the bundle at `bundle_root` whose hash is not in the cache and links
+/// all files into the data directory of the installed bundle at `remote_root`.
+#[instrument(name = "install_resources", skip_all)]
+pub fn install(
+    env: &AndroidEnv,
+    device: &str,
+    bundle_root: &Path,
+    files: &[CachedFile],
+    remote_root: &Path,
+) -> TaiResult<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let cache_dir = cache_dir();
+    adb::mkdir(env, device, &cache_dir)?;
+    let mut cached: HashSet<String> = adb::ls(env, device, &cache_dir)?.into_iter().collect();
+    for file in files {
+        if cached.contains(&file.hash) {
+            continue;
+        }
+        debug!("push {} ({})", file.path.display(), file.hash);
+        // a push that is interrupted must not leave a broken file under the hash
+        let partial = cache_dir.join(format!("{}.partial", file.hash));
+        adb::sync(env, device, bundle_root.join(&file.path), &partial)?;
+        adb::mv(env, device, &partial, cache_dir.join(&file.hash))?;
+        cached.insert(file.hash.clone());
+    }
+
+    link_scripts(&cache_dir, files, remote_root)
+        .iter()
+        .try_for_each(|script| adb::shell(env, device, script))
+}
+
+/// Removes the cache from the device.
+pub fn clean(env: &AndroidEnv, device: &str) -> TaiResult<()> {
+    adb::rm(env, device, cache_dir())
+}
+
+/// The shell commands that create the directories of the files and link the files
+/// into the cache. The commands are split into scripts of at most `MAX_SCRIPT_LEN` bytes.
+fn link_scripts(cache_dir: &Path, files: &[CachedFile], remote_root: &Path) -> Vec<String> {
+    let dirs: BTreeSet<PathBuf> = files
+        .iter()
+        .filter_map(|file| file.path.parent())
+        .map(|dir| remote_root.join(dir))
+        .collect();
+    let commands = dirs
+        .iter()
+        .map(|dir| format!("mkdir -p {}", quote(dir)))
+        .chain(files.iter().map(|file| {
+            format!(
+                "ln -sf {} {}",
+                quote(&cache_dir.join(&file.hash)),
+                quote(&remote_root.join(&file.path))
+            )
+        }));
+
+    let mut scripts: Vec<String> = Vec::new();
+    for command in commands {
+        match scripts.last_mut() {
+            Some(script) if script.len() + command.len() + 4 <= MAX_SCRIPT_LEN => {
+                script.push_str(" && ");
+                script.push_str(&command);
+            }
+            _ => scripts.push(command),
+        }
+    }
+    scripts
+}
+
+fn quote(path: &Path) -> String {
+    adb::quote(&path.to_string_lossy())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_hash_resources() {
+        let bundle = tempfile::tempdir().unwrap();
+        let data_dir = bundle.path().join(tai_util::DATA_DIR_NAME);
+        fs::create_dir_all(data_dir.join("model")).unwrap();
+        fs::write(data_dir.join("test.txt"), "test").unwrap();
+        fs::write(data_dir.join("model/0.bin"), "test").unwrap();
+
+        let files = hash_resources(bundle.path()).unwrap();
+
+        let hash = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        assert_eq!(
+            files,
+            vec![
+                CachedFile {
+                    path: Path::new(tai_util::DATA_DIR_NAME).join("model/0.bin"),
+                    hash: hash.to_string(),
+                },
+                CachedFile {
+                    path: Path::new(tai_util::DATA_DIR_NAME).join("test.txt"),
+                    hash: hash.to_string(),
+                },
+            ]
+        );
+        assert!(hash_resources(&bundle.path().join("empty"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_link_scripts() {
+        let files = [
+            CachedFile {
+                path: PathBuf::from("test-data/model/0.bin"),
+                hash: "aa".to_string(),
+            },
+            CachedFile {
+                path: PathBuf::from("test-data/it's.txt"),
+                hash: "bb".to_string(),
+            },
+        ];
+
+        let scripts = link_scripts(Path::new("/cache"), &files, Path::new("/bundle"));
+
+        assert_eq!(
+            scripts,
+            vec![
+                "mkdir -p '/bundle/test-data' && mkdir -p '/bundle/test-data/model' \
+                && ln -sf '/cache/aa' '/bundle/test-data/model/0.bin' \
+                && ln -sf '/cache/bb' '/bundle/test-data/it'\\''s.txt'"
+            ]
+        );
+
+        let files = vec![files[0].clone(); MAX_SCRIPT_LEN / 40];
+        let scripts = link_scripts(Path::new("/cache"), &files, Path::new("/bundle"));
+        assert_eq!(scripts.len(), 2);
+        assert!(scripts.iter().all(|script| script.len() <= MAX_SCRIPT_LEN));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_pushes_missing_files() {
+        use crate::android::tools::fake_adb::FakeAdb;
+
+        let adb = FakeAdb::new(
+            r#"
+case "$3 $4" in
+    "shell ls") echo "aa" ;;
+    "push --sync") echo "$6" >> "$(dirname "$0")/pushed" ;;
+esac
+exit 0
+"#,
+        );
+        let files = [
+            CachedFile {
+                path: PathBuf::from("test-data/a.txt"),
+                hash: "aa".to_string(),
+            },
+            CachedFile {
+                path: PathBuf::from("test-data/b.txt"),
+                hash: "bb".to_string(),
+            },
+            CachedFile {
+                path: PathBuf::from("test-data/c.txt"),
+                hash: "bb".to_string(),
+            },
+        ];
+
+        install(
+            adb.env(),
+            "emulator-5554",
+            adb.dir(),
+            &files,
+            Path::new("/bundle"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(adb.dir().join("pushed")).unwrap(),
+            format!("{}\n", cache_dir().join("bb.partial").display())
+        );
+    }
+}
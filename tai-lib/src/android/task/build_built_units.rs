@@ -7,6 +7,8 @@ use crate::{
         command::Command,
         compiler::{compile_benches, compile_tests, BuiltUnit},
         opts::Options,
+        project::ProjectMetadata,
+        report::{Event, Reporter},
         task::Task,
     },
     TaiResult,
@@ -23,17 +25,25 @@ impl Task<Context> for BuildBuiltUnits {
         let env: &AndroidEnv = context.get();
         let opts: &Options = context.get();
 
+        let tai_target = &context.get::<ProjectMetadata>().tai_target;
+
         let cmd = match opts.command {
-            Command::Bench => bench_command(env, opts)?,
-            Command::Test => test_command(env, opts)?,
-            Command::Benches => benches_command(env, opts)?,
-            Command::Tests => tests_command(env, opts)?,
+            Command::Bench => bench_command(env, opts, tai_target)?,
+            Command::Test => test_command(env, opts, tai_target)?,
+            Command::Benches => benches_command(env, opts, tai_target)?,
+            Command::Tests => tests_command(env, opts, tai_target)?,
         };
 
         let built_units = match opts.command {
             Command::Bench | Command::Benches => compile_benches(cmd, &opts.compiler)?,
             Command::Test | Command::Tests => compile_tests(cmd, &opts.compiler)?,
         };
+
+        let reporter: &Reporter = context.get();
+        built_units
+            .iter()
+            .for_each(|unit| reporter.emit(&Event::build_artifact(unit)));
+
         context.insert(BuiltUnits(built_units));
 
         Ok(context)
@@ -1,6 +1,12 @@
 use crate::{
     android::bundle::create_bundle,
-    common::{bundle::create_bundles, opts::Options, project::ProjectMetadata, task::Task},
+    common::{
+        bundle::create_bundles,
+        opts::Options,
+        project::ProjectMetadata,
+        report::{Event, Reporter},
+        task::Task,
+    },
     TaiResult,
 };
 
@@ -18,6 +24,12 @@ impl Task<Context> for CreateBundles {
             create_bundle(unit, root, resources)
         })?;
 
+        let reporter: &Reporter = context.get();
+        bundles
+            .bundles
+            .iter()
+            .for_each(|bundle| reporter.emit(&Event::bundle_created(bundle)));
+
         context.insert(bundles);
 
         Ok(context)
@@ -1,8 +1,10 @@
+use std::path::Path;
+
 use anyhow::anyhow;
 
 use crate::{
     android::tools::AndroidEnv,
-    common::{opts::Options, task::Task},
+    common::{opts::Options, project::ProjectMetadata, task::Task},
     TaiResult,
 };
 
@@ -17,7 +19,13 @@ impl Task<Context> for GetAndroidEnv {
             .android
             .as_ref()
             .ok_or_else(|| anyhow!("no ndk"))?;
-        let env = AndroidEnv::derive_env(opts)?;
+        let project_dir = context
+            .get::<ProjectMetadata>()
+            .cargo_opts
+            .manifest_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."));
+        let env = AndroidEnv::derive_env(opts, project_dir)?;
 
         context.insert(env);
 
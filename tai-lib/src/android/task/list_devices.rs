@@ -1,11 +1,14 @@
-use anyhow::bail;
-
 use crate::{
     android::tools::{
         adb::{self, Device},
         AndroidEnv,
     },
-    common::{opts::Options, task::Task},
+    common::{
+        device_selection::{select_devices, DeviceProperties, NoDeviceAvailable},
+        opts::Options,
+        report::{DeviceInfo, Event, Reporter},
+        task::Task,
+    },
     TaiResult,
 };
 
@@ -13,6 +16,10 @@ use super::Context;
 
 pub struct Devices(pub Vec<Device>);
 
+/// The serial of the emulator that was started for `--avd`. Only this emulator is used
+/// if it is set.
+pub struct RequestedEmulator(pub Option<String>);
+
 pub struct ListDevices;
 
 impl Task<Context> for ListDevices {
@@ -21,12 +28,35 @@ impl Task<Context> for ListDevices {
 
         let devices = adb::devices(env)?
             .into_iter()
-            .filter(|device| device.arch == context.get::<Options>().compiler.target.arch)
+            .filter(|device| device.supports(&context.get::<Options>().compiler.target.arch))
+            .filter(|device| match context.get() {
+                RequestedEmulator(Some(serial)) => &device.id == serial,
+                RequestedEmulator(None) => true,
+            })
             .collect::<Vec<Device>>();
 
         if devices.is_empty() {
-            bail!("no android device available")
+            return Err(NoDeviceAvailable("no android device available".to_string()).into());
         }
+        let devices = select_devices(devices, &context.get::<Options>().devices, |device| {
+            DeviceProperties {
+                id: &device.id,
+                model: device.model.as_deref(),
+                api_level: device.api_level,
+                ..Default::default()
+            }
+        })?;
+
+        context.get::<Reporter>().emit(&Event::DevicesSelected {
+            devices: devices
+                .iter()
+                .map(|device| DeviceInfo {
+                    id: device.id.clone(),
+                    name: device.model.clone(),
+                    arch: Some(device.abi.clone()),
+                })
+                .collect(),
+        });
 
         context.insert(Devices(devices));
         Ok(context)
@@ -1,7 +1,5 @@
 use crate::{
-    common::task::{
-        context::Context, get_project_metadata::GetProjectMetadata, set_bench_arg::SetBenchArg,
-    },
+    common::task::{context::Context, set_bench_arg::SetBenchArg},
     TaiResult,
 };
 
@@ -12,8 +10,11 @@ pub mod list_devices;
 pub mod run_on_devices;
 
 pub use self::{
-    build_built_units::BuildBuiltUnits, create_bundles::CreateBundles,
-    get_android_env::GetAndroidEnv, list_devices::ListDevices, run_on_devices::RunOnDevices,
+    build_built_units::BuildBuiltUnits,
+    create_bundles::CreateBundles,
+    get_android_env::GetAndroidEnv,
+    list_devices::{ListDevices, RequestedEmulator},
+    run_on_devices::RunOnDevices,
 };
 
 pub enum Task {
@@ -22,7 +23,6 @@ pub enum Task {
     BuildBuiltUnits(BuildBuiltUnits),
     CreateBundles(CreateBundles),
     RunOnDevices(RunOnDevices),
-    GetProjectMetadata(GetProjectMetadata),
     SetBenchArg(SetBenchArg),
 }
 
@@ -34,7 +34,6 @@ impl crate::common::task::Task<Context> for Task {
             Task::BuildBuiltUnits(task) => task.run(context),
             Task::CreateBundles(task) => task.run(context),
             Task::RunOnDevices(task) => task.run(context),
-            Task::GetProjectMetadata(task) => task.run(context),
             Task::SetBenchArg(task) => task.run(context),
         }
     }
@@ -1,111 +1,757 @@
 use std::{
-    io::Write,
-    path::{Path, PathBuf},
+    io::{self, Write},
+    path::PathBuf,
+    process::Output,
+    sync::Mutex,
+    thread,
+    time::Duration,
 };
 
-use anyhow::bail;
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
 
 use crate::{
-    android::tools::{adb, AndroidEnv},
+    android::{
+        exit_status::{self, EXIT_STATUS_SENTINEL},
+        resource_cache::{self, CachedFile},
+        tools::{
+            adb::{self, Device},
+            AndroidEnv,
+        },
+        ANDROID_REMOTE_WORKDIR,
+    },
     common::{
         bundle::{BuiltBundle, BuiltBundles},
-        opts::{BinaryOptions, Options},
+        opts::{BinaryOptions, Options, RunOptions},
+        report::{BundleResult, Event, Reporter, RunReport, RunStatus},
         task::Task,
+        timeout::Timeouts,
+        tools::command_ext::OutputStream,
     },
     TaiResult,
 };
 
 use super::{list_devices::Devices, Context};
 
-const ANDROID_REMOTE_WORKDIR: &str = "/data/local/tmp/cargo-tai";
+/// The start script writes the pid of the binary to this file in the remote bundle root.
+const PID_FILE: &str = "cargo-tai.pid";
 
 pub struct RunOnDevices;
 
 impl Task<Context> for RunOnDevices {
-    fn run(&self, context: Context) -> TaiResult<Context> {
+    fn run(&self, mut context: Context) -> TaiResult<Context> {
         let env: &AndroidEnv = context.get();
-        let bundles = context.get::<BuiltBundles>();
+        let bundles = hash_bundles(context.get::<BuiltBundles>())?;
+        let opts: &Options = context.get();
         let default = BinaryOptions::default();
-        let binary_opt = match context.get::<Options>().binary.as_ref() {
+        let binary_opt = match opts.binary.as_ref() {
             Some(opts) => opts,
             None => &default,
         };
+        let devices = &context.get::<Devices>().0;
+        let reporter: &Reporter = context.get();
 
-        context.get::<Devices>().0.iter().try_for_each(|device| {
-            bundles
-                .bundles
-                .iter()
-                .try_for_each(|bundle| install_and_run_bundle(env, &device.id, bundle, binary_opt))
-        })?;
+        let mut report = RunReport::default();
+        if opts.run.jobs > 1 && devices.len() > 1 {
+            report.extend(run_on_devices_in_parallel(
+                env, devices, &bundles, binary_opt, &opts.run, reporter,
+            ));
+        } else {
+            for device in devices {
+                report.extend(run_on_device(
+                    env, device, &bundles, binary_opt, &opts.run, reporter, None,
+                ));
+                if report.failed() > 0 && !opts.run.no_fail_fast {
+                    break;
+                }
+            }
+        }
+
+        context.insert(report);
         Ok(context)
     }
 }
 
+/// A bundle and the files of its data directory, which are installed via the resource cache.
+struct CachedBundle<'a> {
+    bundle: &'a BuiltBundle,
+    resources: Vec<CachedFile>,
+}
+
+/// Hashes the resources of the bundles once for all devices.
+fn hash_bundles(bundles: &BuiltBundles) -> TaiResult<Vec<CachedBundle<'_>>> {
+    bundles
+        .bundles
+        .iter()
+        .map(|bundle| {
+            Ok(CachedBundle {
+                bundle,
+                resources: resource_cache::hash_resources(&bundle.root)?,
+            })
+        })
+        .collect()
+}
+
+/// Runs the bundles on up to `jobs` devices at the same time.
+///
+/// Every device is handled by its own worker, so a failure on one device does not
+/// cancel the runs on the other devices. The results are returned in device order.
+fn run_on_devices_in_parallel(
+    env: &AndroidEnv,
+    devices: &[Device],
+    bundles: &[CachedBundle],
+    binary_opt: &BinaryOptions,
+    run_opt: &RunOptions,
+    reporter: &Reporter,
+) -> Vec<BundleResult> {
+    let queue = Mutex::new(devices.iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(devices.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..run_opt.jobs.min(devices.len()) {
+            scope.spawn(|| loop {
+                let next = queue.lock().expect("device queue poisoned").next();
+                let (index, device) = match next {
+                    Some(next) => next,
+                    None => break,
+                };
+                let device_results = run_on_device(
+                    env,
+                    device,
+                    bundles,
+                    binary_opt,
+                    run_opt,
+                    reporter,
+                    Some(&device.id),
+                );
+                results
+                    .lock()
+                    .expect("device results poisoned")
+                    .push((index, device_results));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().expect("device results poisoned");
+    results.sort_by_key(|(index, _)| *index);
+    results
+        .into_iter()
+        .flat_map(|(_, device_results)| device_results)
+        .collect()
+}
+
+/// Runs the bundles on a device. Stops after the first bundle that did not pass
+/// unless `no_fail_fast` is set.
+#[instrument(name = "device", fields(device = %device.id), skip_all)]
+fn run_on_device(
+    env: &AndroidEnv,
+    device: &Device,
+    bundles: &[CachedBundle],
+    binary_opt: &BinaryOptions,
+    run_opt: &RunOptions,
+    reporter: &Reporter,
+    output_prefix: Option<&str>,
+) -> Vec<BundleResult> {
+    let timeouts = Timeouts::start(binary_opt, run_opt);
+    let mut results = Vec::with_capacity(bundles.len());
+    for bundle in bundles {
+        let result = BundleResult::measure(&device.id, &bundle.bundle.build_unit.name, || {
+            if timeouts.device_expired() {
+                return Ok(RunStatus::TimedOut);
+            }
+            install_and_run_bundle(
+                env,
+                &device.id,
+                bundle,
+                binary_opt,
+                timeouts.next_bundle(),
+                reporter,
+                output_prefix,
+            )
+        });
+        reporter.emit(&Event::BundleFinished(result.clone()));
+        let passed = result.status.is_success();
+        results.push(result);
+        if !passed && !run_opt.no_fail_fast {
+            break;
+        }
+    }
+    results
+}
+
 fn install_and_run_bundle(
     env: &AndroidEnv,
     device: &str,
-    bundle: &BuiltBundle,
+    CachedBundle { bundle, resources }: &CachedBundle,
     binary_opt: &BinaryOptions,
-) -> TaiResult<()> {
-    let (remote_root, remote_exe) = install_bundle(env, device, bundle)?;
-    let result = run_bundle(env, device, binary_opt, &remote_root, &remote_exe)?;
+    timeout: Option<Duration>,
+    reporter: &Reporter,
+    output_prefix: Option<&str>,
+) -> TaiResult<RunStatus> {
+    let remote = install_bundle(env, device, bundle, resources)?;
+    let result = run_bundle(
+        env,
+        device,
+        binary_opt,
+        &remote,
+        timeout,
+        reporter,
+        output_prefix,
+    );
 
-    adb::rm(env, device, &remote_root)?;
+    let status = match result {
+        Ok(Some(output)) => {
+            reporter.write_junit(device, &bundle.build_unit.name, &output.stdout);
+            match exit_status::parse_exit_status(&output.stdout) {
+                Some(status) => Ok(status.into()),
+                None => Ok(RunStatus::Error {
+                    message: format!(
+                        "no exit status received from the device (adb exited with {})",
+                        output.status
+                    ),
+                }),
+            }
+        }
+        Ok(None) => {
+            warn!(
+                "{} did not finish within {:?}",
+                bundle.build_unit.name,
+                timeout.unwrap_or_default()
+            );
+            adb::kill(env, device, remote.root.join(PID_FILE)).map(|_| RunStatus::TimedOut)
+        }
+        Err(err) => Err(err),
+    };
 
-    if result.status.success() {
-        Ok(())
-    } else {
-        bail!("test failed")
+    if let Some((remote_dir, local_dir)) = reporter.artifacts(device, &bundle.build_unit.name) {
+        let remote_dir = remote.root.join(remote_dir);
+        debug!(
+            "copy artifacts from: {} to: {}",
+            remote_dir.display(),
+            local_dir.display()
+        );
+        if let Err(err) = adb::pull(env, device, &remote_dir, &local_dir) {
+            warn!(
+                "failed to copy the artifacts {}: {}",
+                remote_dir.display(),
+                err
+            );
+        }
     }
+    adb::rm(env, device, &remote.root)?;
+    status
+}
+
+/// The location of an installed bundle on the device.
+struct RemoteBundle {
+    root: PathBuf,
+    exe: PathBuf,
 }
 
-#[instrument(name = "install", skip(env, bundle))]
+/// Installs the executable of the bundle and links its resources from the resource cache.
+#[instrument(name = "install", skip(env, bundle, resources))]
 fn install_bundle(
     env: &AndroidEnv,
     device: &str,
     bundle: &BuiltBundle,
-) -> TaiResult<(PathBuf, PathBuf)> {
-    let remote_workdir = PathBuf::from(ANDROID_REMOTE_WORKDIR);
-    adb::mkdir(env, device, &remote_workdir)?;
-    let remote_root = remote_workdir.join(bundle.root.file_name().unwrap());
+    resources: &[CachedFile],
+) -> TaiResult<RemoteBundle> {
+    let remote_root = PathBuf::from(ANDROID_REMOTE_WORKDIR).join(bundle.root.file_name().unwrap());
+    adb::mkdir(env, device, &remote_root)?;
+    let local_exe = bundle.root.join(&bundle.build_unit.name);
+    let remote_exe = remote_root.join(&bundle.build_unit.name);
     debug!(
         "copy from: {} to: {}",
-        bundle.root.display(),
-        remote_root.display()
+        local_exe.display(),
+        remote_exe.display()
     );
-    adb::sync(env, device, &bundle.root, &remote_root)?;
-    let remote_exe = remote_root.join(&bundle.build_unit.name);
+    adb::sync(env, device, &local_exe, &remote_exe)?;
+    resource_cache::install(env, device, &bundle.root, resources, &remote_root)?;
     debug!("chmod {}", remote_exe.display());
     adb::chmod(env, device, &remote_exe)?;
-    Ok((remote_root, remote_exe))
+    Ok(RemoteBundle {
+        root: remote_root,
+        exe: remote_exe,
+    })
 }
 
 fn run_bundle(
     env: &AndroidEnv,
     device: &str,
     binary_opt: &BinaryOptions,
-    remote_root: &Path,
-    remote_exe: &Path,
-) -> TaiResult<std::process::Output> {
-    let envs_as_string = if let Some(envs) = &binary_opt.envs {
-        envs.iter()
-            .map(|(key, value)| format!("{}={}", key, value))
-            .collect::<Vec<String>>()
-            .join(" ")
-    } else {
-        String::from("")
-    };
+    remote: &RemoteBundle,
+    timeout: Option<Duration>,
+    reporter: &Reporter,
+    output_prefix: Option<&str>,
+) -> TaiResult<Option<Output>> {
+    let start_script = start_script(binary_opt, remote);
+    adb::run(
+        env,
+        device,
+        &start_script,
+        timeout,
+        |stream, line| match stream {
+            OutputStream::Stdout => write_line(&mut reporter.human_output(), line, output_prefix),
+            OutputStream::Stderr => write_line(&mut io::stderr(), line, output_prefix),
+        },
+    )
+}
+
+/// The script that starts the binary with `adb shell`. The device shell parses the script,
+/// so the arguments and the values of the environment variables are quoted.
+fn start_script(binary_opt: &BinaryOptions, remote: &RemoteBundle) -> String {
+    let envs = binary_opt
+        .envs
+        .iter()
+        .flatten()
+        .map(|(key, value)| format!("{}={}", key, adb::quote(value)))
+        .collect::<Vec<String>>()
+        .join(" ");
+    let args = binary_opt
+        .binary_args()
+        .iter()
+        .map(|arg| adb::quote(arg))
+        .collect::<Vec<String>>()
+        .join(" ");
 
-    let start_script = format!(
+    format!(
         include_str!("../templates/start_script.tmpl"),
-        remote_bundle_root = remote_root.to_string_lossy(),
-        envs = envs_as_string,
-        remote_executable = remote_exe.to_string_lossy(),
-        args = binary_opt.args.as_ref().unwrap_or(&vec![]).join(" ")
-    );
-    let result = adb::run(env, device, &start_script)?;
-    let _ = std::io::stdout().write(result.stdout.as_slice());
-    let _ = std::io::stderr().write(result.stderr.as_slice());
-    Ok(result)
+        remote_bundle_root = remote.root.to_string_lossy(),
+        envs = envs,
+        remote_executable = remote.exe.to_string_lossy(),
+        pid_file = PID_FILE,
+        exit_status_sentinel = EXIT_STATUS_SENTINEL,
+        args = args
+    )
+}
+
+/// Writes a line of the output of a bundle, prefixing it when the output of several
+/// devices is interleaved. The exit status sentinel of the start script is not written.
+fn write_line<W: Write>(out: &mut W, line: &[u8], prefix: Option<&str>) {
+    let line = match find_subslice(line, EXIT_STATUS_SENTINEL.as_bytes()) {
+        Some(0) => return,
+        // the output of the binary did not end with a line break
+        Some(sentinel) => &line[..sentinel],
+        None => line,
+    };
+    let _ = match prefix {
+        Some(prefix) => writeln!(
+            out,
+            "[{}] {}",
+            prefix,
+            String::from_utf8_lossy(line).trim_end_matches(&['\r', '\n'][..])
+        ),
+        None => out.write_all(line).and_then(|_| out.flush()),
+    };
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{fs, path::Path};
+
+    use cfg_expr::targets::{get_builtin_target_by_triple, Arch};
+
+    use crate::{android::tools::fake_adb::FakeAdb, common::compiler::BuiltUnit};
+
+    use super::*;
+
+    fn bundles(root: &Path, names: &[&str]) -> BuiltBundles {
+        let bundles = names
+            .iter()
+            .map(|name| {
+                let bundle_root = root.join(name);
+                fs::create_dir_all(&bundle_root).unwrap();
+                let artifact = bundle_root.join(name);
+                fs::write(&artifact, "").unwrap();
+
+                BuiltBundle {
+                    root: bundle_root,
+                    build_unit: BuiltUnit {
+                        name: name.to_string(),
+                        target_name: name.to_string(),
+                        artifact,
+                        target: get_builtin_target_by_triple("aarch64-linux-android")
+                            .unwrap()
+                            .to_owned(),
+                    },
+                }
+            })
+            .collect();
+        BuiltBundles { bundles }
+    }
+
+    fn devices(ids: &[&str]) -> Vec<Device> {
+        ids.iter()
+            .map(|id| Device {
+                id: id.to_string(),
+                abi: "arm64-v8a".to_string(),
+                abis: vec!["arm64-v8a".to_string()],
+                archs: vec![Arch::aarch64],
+                model: None,
+                api_level: None,
+                os_version: None,
+            })
+            .collect()
+    }
+
+    fn run_opt(jobs: usize, no_fail_fast: bool) -> RunOptions {
+        RunOptions {
+            jobs,
+            no_fail_fast,
+            ..Default::default()
+        }
+    }
+
+    fn reporter() -> Reporter {
+        Reporter::new(&RunOptions::default(), Path::new("target/cargo-tai")).unwrap()
+    }
+
+    fn summary(results: &[BundleResult]) -> Vec<(&str, &str, bool)> {
+        results
+            .iter()
+            .map(|result| {
+                (
+                    result.device.as_str(),
+                    result.bundle.as_str(),
+                    result.status.is_success(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_run_on_devices_in_parallel() {
+        let adb = FakeAdb::new(
+            r#"
+case "$3 $4" in
+    # remember on which device the bundle was started
+    "shell cd "*)
+        echo "$2" >> "$(dirname "$0")/runs"
+        echo "cargo-tai-exit-status:0"
+        ;;
+esac
+exit 0
+"#,
+        );
+        let bundles = bundles(adb.dir(), &["integration-1234"]);
+        let devices = devices(&["emulator-5554", "emulator-5556", "R58M123"]);
+
+        let results = run_on_devices_in_parallel(
+            adb.env(),
+            &devices,
+            &hash_bundles(&bundles).unwrap(),
+            &BinaryOptions::default(),
+            &run_opt(usize::MAX, false),
+            &reporter(),
+        );
+
+        assert_eq!(
+            summary(&results),
+            vec![
+                ("emulator-5554", "integration-1234", true),
+                ("emulator-5556", "integration-1234", true),
+                ("R58M123", "integration-1234", true),
+            ]
+        );
+
+        let mut runs = fs::read_to_string(adb.dir().join("runs"))
+            .unwrap()
+            .lines()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        runs.sort();
+        assert_eq!(runs, vec!["R58M123", "emulator-5554", "emulator-5556"]);
+    }
+
+    #[test]
+    fn test_failing_device_does_not_cancel_other_devices() {
+        let adb = FakeAdb::new(
+            r#"
+case "$2 $3 $4" in
+    "emulator-5556 shell cd "*) echo "cargo-tai-exit-status:101" ;;
+    *" shell cd "*) echo "cargo-tai-exit-status:0" ;;
+esac
+exit 0
+"#,
+        );
+        let bundles = bundles(adb.dir(), &["integration-1234"]);
+        let devices = devices(&["emulator-5554", "emulator-5556", "R58M123"]);
+
+        let results = run_on_devices_in_parallel(
+            adb.env(),
+            &devices,
+            &hash_bundles(&bundles).unwrap(),
+            &BinaryOptions::default(),
+            &run_opt(2, false),
+            &reporter(),
+        );
+
+        assert_eq!(
+            summary(&results),
+            vec![
+                ("emulator-5554", "integration-1234", true),
+                ("emulator-5556", "integration-1234", false),
+                ("R58M123", "integration-1234", true),
+            ]
+        );
+        assert_eq!(
+            results[1].status,
+            RunStatus::Failed {
+                exit_code: Some(101)
+            }
+        );
+    }
+
+    #[test]
+    fn test_run_on_device_exit_status() {
+        // like older adb versions, the fake adb does not propagate the exit status
+        let adb = FakeAdb::new(
+            r#"
+case "$3 $4" in
+    "shell cd "*lib-abcd*) printf "running 1 test\ncargo-tai-exit-status:139\n" ;;
+    "shell cd "*) echo "running 1 test" ;;
+esac
+exit 0
+"#,
+        );
+        let bundles = bundles(adb.dir(), &["lib-abcd", "integration-1234"]);
+        let device = &devices(&["emulator-5554"])[0];
+
+        let results = run_on_device(
+            adb.env(),
+            device,
+            &hash_bundles(&bundles).unwrap(),
+            &BinaryOptions::default(),
+            &run_opt(1, true),
+            &reporter(),
+            None,
+        );
+
+        assert_eq!(results[0].status, RunStatus::Crashed { signal: 11 });
+        assert_eq!(
+            results[1].status,
+            RunStatus::Error {
+                message: "no exit status received from the device (adb exited with exit status: 0)"
+                    .to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_run_on_device_timeout() {
+        let adb = FakeAdb::new(
+            r#"
+echo "$*" >> "$(dirname "$0")/calls"
+case "$3 $4" in
+    "shell cd "*) exec sleep 10 ;;
+esac
+exit 0
+"#,
+        );
+        let bundles = bundles(adb.dir(), &["integration-1234"]);
+        let device = &devices(&["emulator-5554"])[0];
+        let binary_opt = BinaryOptions {
+            timeout: Some(Duration::from_millis(300)),
+            ..Default::default()
+        };
+
+        let results = run_on_device(
+            adb.env(),
+            device,
+            &hash_bundles(&bundles).unwrap(),
+            &binary_opt,
+            &run_opt(1, false),
+            &reporter(),
+            None,
+        );
+
+        assert_eq!(results[0].status, RunStatus::TimedOut);
+        // the start script spans several lines, only look at the lines with adb arguments
+        let calls = fs::read_to_string(adb.dir().join("calls")).unwrap();
+        let calls = calls
+            .lines()
+            .filter(|call| call.starts_with("-s "))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            calls[calls.len() - 2..],
+            [
+                "-s emulator-5554 shell kill -9 $(cat /data/local/tmp/cargo-tai/integration-1234/cargo-tai.pid)",
+                "-s emulator-5554 shell rm -rf /data/local/tmp/cargo-tai/integration-1234",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_on_device_pulls_artifacts() {
+        let adb = FakeAdb::new(
+            r#"
+echo "$*" >> "$(dirname "$0")/calls"
+case "$3 $4" in
+    "shell cd "*) echo "cargo-tai-exit-status:0" ;;
+esac
+exit 0
+"#,
+        );
+        let bundles = bundles(adb.dir(), &["integration-1234"]);
+        let device = &devices(&["emulator-5554"])[0];
+        let tai_target = adb.dir().join("target/cargo-tai");
+        let reporter = Reporter::new(
+            &RunOptions {
+                artifacts: Some(PathBuf::from("artifacts")),
+                ..Default::default()
+            },
+            &tai_target,
+        )
+        .unwrap();
+
+        let results = run_on_device(
+            adb.env(),
+            device,
+            &hash_bundles(&bundles).unwrap(),
+            &BinaryOptions::default(),
+            &run_opt(1, false),
+            &reporter,
+            None,
+        );
+
+        assert!(results[0].status.is_success());
+        let calls = fs::read_to_string(adb.dir().join("calls")).unwrap();
+        let calls = calls
+            .lines()
+            .filter(|call| call.starts_with("-s "))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            calls[calls.len() - 2..],
+            [
+                format!(
+                    "-s emulator-5554 pull /data/local/tmp/cargo-tai/integration-1234/artifacts {}",
+                    tai_target
+                        .join("artifacts/emulator-5554/integration-1234")
+                        .display()
+                ),
+                "-s emulator-5554 shell rm -rf /data/local/tmp/cargo-tai/integration-1234"
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_on_device_device_timeout() {
+        let adb = FakeAdb::new("exit 0");
+        let bundles = bundles(adb.dir(), &["lib-abcd", "integration-1234"]);
+        let device = &devices(&["emulator-5554"])[0];
+        let run_opt = RunOptions {
+            no_fail_fast: true,
+            device_timeout: Some(Duration::ZERO),
+            ..Default::default()
+        };
+
+        let results = run_on_device(
+            adb.env(),
+            device,
+            &hash_bundles(&bundles).unwrap(),
+            &BinaryOptions::default(),
+            &run_opt,
+            &reporter(),
+            None,
+        );
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| &result.status)
+                .collect::<Vec<_>>(),
+            vec![&RunStatus::TimedOut, &RunStatus::TimedOut]
+        );
+    }
+
+    #[test]
+    fn test_run_on_device_fail_fast() {
+        let adb = FakeAdb::new(
+            r#"
+case "$3 $4" in
+    "shell cd "*lib-abcd*) echo "cargo-tai-exit-status:101" ;;
+    "shell cd "*) echo "cargo-tai-exit-status:0" ;;
+esac
+exit 0
+"#,
+        );
+        let bundles = bundles(adb.dir(), &["lib-abcd", "integration-1234"]);
+        let device = &devices(&["emulator-5554"])[0];
+
+        let results = run_on_device(
+            adb.env(),
+            device,
+            &hash_bundles(&bundles).unwrap(),
+            &BinaryOptions::default(),
+            &run_opt(1, false),
+            &reporter(),
+            None,
+        );
+        assert_eq!(
+            summary(&results),
+            vec![("emulator-5554", "lib-abcd", false)]
+        );
+
+        let results = run_on_device(
+            adb.env(),
+            device,
+            &hash_bundles(&bundles).unwrap(),
+            &BinaryOptions::default(),
+            &run_opt(1, true),
+            &reporter(),
+            None,
+        );
+        assert_eq!(
+            summary(&results),
+            vec![
+                ("emulator-5554", "lib-abcd", false),
+                ("emulator-5554", "integration-1234", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_start_script_quotes_args() {
+        let binary_opt = BinaryOptions {
+            args: Some(vec!["--bench".to_string()]),
+            envs: Some(vec![("NAME".to_string(), "a b".to_string())]),
+            filter: Some("it's; rm -rf $HOME".to_string()),
+            skip: Some(vec!["a|b".to_string()]),
+            ..Default::default()
+        };
+        let remote = RemoteBundle {
+            root: PathBuf::from("/data/local/tmp/cargo-tai/integration"),
+            exe: PathBuf::from("/data/local/tmp/cargo-tai/integration/integration"),
+        };
+
+        let script = start_script(&binary_opt, &remote);
+
+        assert!(script.contains(
+            "NAME='a b' /data/local/tmp/cargo-tai/integration/integration \
+            '--bench' '--skip' 'a|b' 'it'\\''s; rm -rf $HOME' &"
+        ));
+    }
+
+    #[test]
+    fn test_write_line_with_prefix() {
+        let mut out = Vec::new();
+        write_line(&mut out, b"running 1 test\n", Some("R58M123"));
+        write_line(
+            &mut out,
+            b"test a ... okcargo-tai-exit-status:0\n",
+            Some("R58M123"),
+        );
+        write_line(&mut out, b"cargo-tai-exit-status:0\n", Some("R58M123"));
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[R58M123] running 1 test\n[R58M123] test a ... ok\n"
+        );
+    }
 }
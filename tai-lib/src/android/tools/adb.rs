@@ -1,26 +1,74 @@
 use std::{
+    collections::HashMap,
     io::ErrorKind,
     path::Path,
     process::{Command, Output},
+    time::Duration,
 };
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use cfg_expr::targets::Arch;
 use once_cell::sync::OnceCell;
 
-use crate::{common::tools::command_ext::ExitStatusExt, TaiResult};
+use crate::{
+    common::tools::command_ext::{CommandExt, ExitStatusExt, OutputStream},
+    TaiResult,
+};
 
 use super::AndroidEnv;
 
 static DEVICE_REGEX: OnceCell<regex::Regex> = OnceCell::new();
+static PROPERTY_REGEX: OnceCell<regex::Regex> = OnceCell::new();
 
 #[derive(Debug)]
 pub struct Device {
     pub id: String,
-    pub arch: Arch<'static>,
+    /// `ro.product.cpu.abi`, e.g. `arm64-v8a`
+    pub abi: String,
+    /// `ro.product.cpu.abilist`, e.g. `arm64-v8a,armeabi-v7a,armeabi`
+    pub abis: Vec<String>,
+    /// The architectures of the ABIs that cargo-tai supports, in the order of `abis`.
+    /// Empty if the device does not support any of them.
+    pub archs: Vec<Arch<'static>>,
+    /// `ro.product.model`, e.g. `Pixel 6`
+    pub model: Option<String>,
+    /// `ro.build.version.sdk`
+    pub api_level: Option<u32>,
+    /// `ro.build.version.release`, e.g. `13`
+    pub os_version: Option<String>,
 }
 
 pub fn devices(env: &AndroidEnv) -> TaiResult<Vec<Device>> {
+    serials(env)?
+        .into_iter()
+        .map(|id| {
+            let mut properties = properties(env, &id)?;
+            let abi = properties.remove("ro.product.cpu.abi").unwrap_or_default();
+            let abis = match properties.remove("ro.product.cpu.abilist") {
+                Some(abilist) if !abilist.is_empty() => {
+                    abilist.split(',').map(str::to_owned).collect()
+                }
+                _ => vec![abi.clone()],
+            };
+            let archs = supported_archs(&abis);
+
+            Ok(Device {
+                id,
+                abi,
+                abis,
+                archs,
+                model: properties.remove("ro.product.model"),
+                api_level: properties
+                    .get("ro.build.version.sdk")
+                    .and_then(|sdk| sdk.parse().ok()),
+                os_version: properties.remove("ro.build.version.release"),
+            })
+        })
+        .collect()
+}
+
+/// The serials of the devices that are online.
+pub fn serials(env: &AndroidEnv) -> TaiResult<Vec<String>> {
     let output = Command::new(&env.adb)
         .arg("devices")
         .output()
@@ -34,27 +82,81 @@ pub fn devices(env: &AndroidEnv) -> TaiResult<Vec<Device>> {
     let device_regex =
         DEVICE_REGEX.get_or_init(|| regex::Regex::new(r#"^(\S+)\tdevice\r?$"#).unwrap());
 
-    String::from_utf8(output.stdout)?
+    Ok(String::from_utf8(output.stdout)?
         .split('\n')
         .skip(1)
-        .into_iter()
-        .try_fold(vec![], |mut devices, line| {
-            if let Some(caps) = device_regex.captures(line) {
-                let id = caps[1].to_owned();
-                let arch = arch(env, &id)?;
+        .filter_map(|line| device_regex.captures(line).map(|caps| caps[1].to_owned()))
+        .collect())
+}
+
+impl Device {
+    /// Returns `true` if the device can run binaries built for `arch`.
+    pub fn supports(&self, arch: &Arch) -> bool {
+        self.archs.contains(arch)
+    }
+}
 
-                devices.push(Device { id, arch })
+fn supported_archs(abis: &[String]) -> Vec<Arch<'static>> {
+    let mut archs = Vec::new();
+    for abi in abis {
+        if let Ok(arch) = Arch::try_from(CpuArch::from(abi.as_str())) {
+            if !archs.contains(&arch) {
+                archs.push(arch);
             }
-            Ok(devices)
-        })
+        }
+    }
+    archs
 }
 
-pub fn arch(env: &AndroidEnv, device: &str) -> TaiResult<Arch<'static>> {
+/// Reads the system properties of the device (`getprop`).
+pub fn properties(env: &AndroidEnv, device: &str) -> TaiResult<HashMap<String, String>> {
     let output = Command::new(&env.adb)
-        .args(["-s", device, "shell", "getprop", "ro.product.cpu.abi"])
+        .args(["-s", device, "shell", "getprop"])
         .output()?;
-    let cpu_arch: CpuArch = String::from_utf8(output.stdout)?.trim().into();
-    Ok(cpu_arch.into())
+    let property_regex =
+        PROPERTY_REGEX.get_or_init(|| regex::Regex::new(r#"^\[(.+)\]: \[(.*)\]\r?$"#).unwrap());
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| property_regex.captures(line))
+        .map(|caps| (caps[1].to_owned(), caps[2].to_owned()))
+        .collect())
+}
+
+/// Reads a system property of the device, e.g. `sys.boot_completed`.
+pub fn getprop(env: &AndroidEnv, device: &str, name: &str) -> TaiResult<String> {
+    let output = Command::new(&env.adb)
+        .args(["-s", device, "shell", "getprop", name])
+        .output()?;
+    output
+        .status
+        .expect_success("failed to read system property")?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The name of the AVD that the emulator runs (`adb emu avd name`).
+pub fn emu_avd_name(env: &AndroidEnv, device: &str) -> TaiResult<String> {
+    let output = Command::new(&env.adb)
+        .args(["-s", device, "emu", "avd", "name"])
+        .output()?;
+    output
+        .status
+        .expect_success("failed to read the AVD name")?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string())
+}
+
+/// Shuts the emulator down (`adb emu kill`).
+pub fn emu_kill(env: &AndroidEnv, device: &str) -> TaiResult<()> {
+    Command::new(&env.adb)
+        .args(["-s", device, "emu", "kill"])
+        .output()?
+        .status
+        .expect_success("failed to kill the emulator")
 }
 
 pub fn mkdir<P: AsRef<Path>>(env: &AndroidEnv, device: &str, path: P) -> TaiResult<()> {
@@ -78,6 +180,21 @@ pub fn sync<FP: AsRef<Path>, TP: AsRef<Path>>(
         .expect_success("failed to sync files")
 }
 
+/// Copies a file or directory from the device. The output of `adb` is not printed.
+pub fn pull<FP: AsRef<Path>, TP: AsRef<Path>>(
+    env: &AndroidEnv,
+    device: &str,
+    from: FP,
+    to: TP,
+) -> TaiResult<()> {
+    Command::new(&env.adb)
+        .args(["-s", device, "pull"])
+        .args([from.as_ref(), to.as_ref()])
+        .output()?
+        .status
+        .expect_success("failed to pull files")
+}
+
 pub fn rm<P: AsRef<Path>>(env: &AndroidEnv, device: &str, path: P) -> TaiResult<()> {
     Command::new(&env.adb)
         .args(["-s", device, "shell", "rm", "-rf"])
@@ -86,6 +203,40 @@ pub fn rm<P: AsRef<Path>>(env: &AndroidEnv, device: &str, path: P) -> TaiResult<
         .expect_success("failed to remove files/directories")
 }
 
+pub fn mv<FP: AsRef<Path>, TP: AsRef<Path>>(
+    env: &AndroidEnv,
+    device: &str,
+    from: FP,
+    to: TP,
+) -> TaiResult<()> {
+    Command::new(&env.adb)
+        .args(["-s", device, "shell", "mv"])
+        .args([from.as_ref(), to.as_ref()])
+        .status()?
+        .expect_success("failed to move file")
+}
+
+/// Lists the names of the entries of a directory on the device.
+pub fn ls<P: AsRef<Path>>(env: &AndroidEnv, device: &str, path: P) -> TaiResult<Vec<String>> {
+    let output = Command::new(&env.adb)
+        .args(["-s", device, "shell", "ls"])
+        .arg(path.as_ref())
+        .output()?;
+    output.status.expect_success("failed to list directory")?;
+    Ok(String::from_utf8(output.stdout)?
+        .split_whitespace()
+        .map(str::to_owned)
+        .collect())
+}
+
+/// Runs a shell command on the device.
+pub fn shell(env: &AndroidEnv, device: &str, command: &str) -> TaiResult<()> {
+    Command::new(&env.adb)
+        .args(["-s", device, "shell", command])
+        .status()?
+        .expect_success("failed to run shell command")
+}
+
 pub fn chmod<P: AsRef<Path>>(env: &AndroidEnv, device: &str, path: P) -> TaiResult<()> {
     Command::new(&env.adb)
         .args(["-s", device, "shell", "chmod", "755"])
@@ -94,14 +245,42 @@ pub fn chmod<P: AsRef<Path>>(env: &AndroidEnv, device: &str, path: P) -> TaiResu
         .expect_success("failed to chmod file/directory")
 }
 
-pub fn run(env: &AndroidEnv, device: &str, start_script: &str) -> TaiResult<Output> {
+/// Runs the start script on the device. `on_line` is called for every line of output
+/// while the script is running; the returned [`Output`] contains a copy of the whole output.
+///
+/// Returns `None` if the script did not finish within `timeout`. Only the local `adb` process
+/// is killed in that case, see [`kill`].
+pub fn run<F>(
+    env: &AndroidEnv,
+    device: &str,
+    start_script: &str,
+    timeout: Option<Duration>,
+    on_line: F,
+) -> TaiResult<Option<Output>>
+where
+    F: Fn(OutputStream, &[u8]) + Sync,
+{
     Command::new(&env.adb)
         .args(["-s", device, "shell"])
         .arg(start_script)
-        .output()
+        .output_streamed(timeout, on_line)
         .map_err(|err| anyhow!("{}", err))
 }
 
+/// Quotes `arg` for the shell of the device, which parses the commands of `adb shell`.
+pub fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Kills the process whose pid is stored in `pid_file` on the device.
+pub fn kill<P: AsRef<Path>>(env: &AndroidEnv, device: &str, pid_file: P) -> TaiResult<()> {
+    Command::new(&env.adb)
+        .args(["-s", device, "shell"])
+        .arg(format!("kill -9 $(cat {})", pid_file.as_ref().display()))
+        .status()?
+        .expect_success("failed to kill process")
+}
+
 // #TODO replace with https://github.com/rust-windowing/android-ndk-rs/blob/master/ndk-build/src/target.rs
 
 #[derive(Debug, Clone)]
@@ -125,38 +304,136 @@ impl From<&str> for CpuArch {
     }
 }
 
-impl From<CpuArch> for &str {
-    fn from(arch: CpuArch) -> Self {
-        match arch {
+impl CpuArch {
+    /// The name of the ABI, e.g. `arm64-v8a`
+    pub fn as_str(&self) -> &str {
+        match self {
             CpuArch::Aarch64 => "arm64-v8a",
             CpuArch::ARMv7 => "armeabi-v7a",
             CpuArch::X86 => "x86",
             CpuArch::X86_64 => "x86_64",
-            CpuArch::Unsupported(_) => panic!(""),
+            CpuArch::Unsupported(abi) => abi,
         }
     }
 }
 
-impl From<CpuArch> for Arch<'_> {
-    fn from(arch: CpuArch) -> Self {
+impl TryFrom<CpuArch> for Arch<'_> {
+    type Error = anyhow::Error;
+
+    fn try_from(arch: CpuArch) -> Result<Self, Self::Error> {
         match arch {
-            CpuArch::Aarch64 => Arch::aarch64,
-            CpuArch::ARMv7 => Arch::arm,
-            CpuArch::X86 => Arch::x86,
-            CpuArch::X86_64 => Arch::x86_64,
-            CpuArch::Unsupported(_) => panic!(""),
+            CpuArch::Aarch64 => Ok(Arch::aarch64),
+            CpuArch::ARMv7 => Ok(Arch::arm),
+            CpuArch::X86 => Ok(Arch::x86),
+            CpuArch::X86_64 => Ok(Arch::x86_64),
+            CpuArch::Unsupported(abi) => bail!("unsupported Android ABI `{}`", abi),
         }
     }
 }
 
-impl From<Arch<'_>> for CpuArch {
-    fn from(arch: Arch) -> Self {
+impl TryFrom<Arch<'_>> for CpuArch {
+    type Error = anyhow::Error;
+
+    fn try_from(arch: Arch) -> Result<Self, Self::Error> {
         match arch {
-            Arch::aarch64 => CpuArch::Aarch64,
-            Arch::arm => CpuArch::ARMv7,
-            Arch::x86 => CpuArch::X86,
-            Arch::x86_64 => CpuArch::X86_64,
-            _ => panic!(""),
+            Arch::aarch64 => Ok(CpuArch::Aarch64),
+            Arch::arm => Ok(CpuArch::ARMv7),
+            Arch::x86 => Ok(CpuArch::X86),
+            Arch::x86_64 => Ok(CpuArch::X86_64),
+            arch => bail!("no Android ABI for the architecture `{}`", arch.0),
         }
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    use crate::android::tools::fake_adb::FakeAdb;
+
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("--nocapture"), "'--nocapture'");
+        assert_eq!(quote("a b; $HOME"), "'a b; $HOME'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote(""), "''");
+    }
+
+    #[test]
+    fn test_devices() {
+        let adb = FakeAdb::new(
+            r#"
+case "$*" in
+    devices) printf 'List of devices attached\nemulator-5554\tdevice\nR58M123\tdevice\n0123\toffline\nmips-1\tdevice\n\n' ;;
+    "-s mips-1 shell getprop") printf '[ro.product.cpu.abi]: [mips]\n' ;;
+    "-s emulator-5554 shell getprop") printf '[ro.build.version.sdk]: [33]\n[ro.product.cpu.abi]: [x86_64]\n[ro.product.model]: [sdk_gphone64_x86_64]\n' ;;
+    "-s R58M123 shell getprop") printf '[ro.product.cpu.abi]: [arm64-v8a]\r\n[ro.product.cpu.abilist]: [arm64-v8a,armeabi-v7a,armeabi]\r\n[ro.product.model]: [Pixel 6]\r\n' ;;
+esac
+"#,
+        );
+
+        let devices = devices(adb.env()).unwrap();
+
+        assert_eq!(devices.len(), 3);
+        assert_eq!(devices[0].id, "emulator-5554");
+        assert_eq!(devices[0].archs, vec![Arch::x86_64]);
+        assert_eq!(devices[0].model.as_deref(), Some("sdk_gphone64_x86_64"));
+        assert_eq!(devices[0].api_level, Some(33));
+        assert_eq!(devices[1].id, "R58M123");
+        assert_eq!(devices[1].archs, vec![Arch::aarch64, Arch::arm]);
+        assert!(devices[1].supports(&Arch::arm));
+        assert_eq!(devices[1].model.as_deref(), Some("Pixel 6"));
+        assert_eq!(devices[1].api_level, None);
+        assert_eq!(devices[2].abi, "mips");
+        assert!(devices[2].archs.is_empty());
+    }
+
+    #[test]
+    fn test_cpu_arch_conversions() {
+        assert_eq!(
+            Arch::try_from(CpuArch::from("armeabi-v7a")).unwrap(),
+            Arch::arm
+        );
+        assert!(Arch::try_from(CpuArch::from("riscv64")).is_err());
+        assert_eq!(CpuArch::from("riscv64").as_str(), "riscv64");
+        assert_eq!(
+            CpuArch::try_from(Arch::aarch64).unwrap().as_str(),
+            "arm64-v8a"
+        );
+        assert!(CpuArch::try_from(Arch::riscv64).is_err());
+    }
+
+    #[test]
+    fn test_run_streams_output() {
+        let adb = FakeAdb::new(
+            r#"
+echo "running 1 test"
+sleep 1
+echo "test a ... ok"
+"#,
+        );
+
+        let start = Instant::now();
+        let first_line = Mutex::new(None);
+        let output = run(adb.env(), "emulator-5554", "./start", None, |_, line| {
+            let mut first_line = first_line.lock().unwrap();
+            if first_line.is_none() {
+                *first_line = Some((start.elapsed(), line.to_vec()));
+            }
+        })
+        .unwrap()
+        .unwrap();
+        let total = start.elapsed();
+
+        let (first_line_at, first_line) = first_line.into_inner().unwrap().unwrap();
+        assert_eq!(first_line, b"running 1 test\n");
+        // the first line arrives before the fake adb has exited
+        assert!(first_line_at + Duration::from_millis(500) < total);
+        assert_eq!(output.stdout, b"running 1 test\ntest a ... ok\n");
+    }
+}
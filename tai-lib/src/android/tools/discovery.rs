@@ -0,0 +1,439 @@
+//! Finds the android sdk, the ndk and `adb` if they are not given explicitly.
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+
+use crate::TaiResult;
+
+use super::{
+    ndk::{ndk_version, NdkVersion},
+    read_properties,
+};
+
+const LOCAL_PROPERTIES: &str = "local.properties";
+
+/// The result of [`Discovery::run`].
+#[derive(Debug, Default)]
+pub struct Discovery {
+    pub sdk: Option<PathBuf>,
+    pub ndk: Option<PathBuf>,
+    pub adb: Option<PathBuf>,
+    /// The candidates that were rejected, e.g. `ANDROID_HOME: /opt/sdk (does not exist)`
+    tried: Vec<String>,
+}
+
+impl Discovery {
+    /// Looks for the sdk, the ndk and `adb` in this order:
+    ///
+    /// - sdk: `sdk`, `ANDROID_HOME`, `ANDROID_SDK_ROOT`, `sdk.dir` of the `local.properties`
+    ///   nearest to `project_dir`, the sdk that contains `ndk` or `ANDROID_NDK_ROOT` and the default
+    ///   location of the OS
+    /// - ndk: `ndk`, `ANDROID_NDK_ROOT`, `ndk.dir` of the nearest `local.properties`,
+    ///   the newest ndk in `<sdk>/ndk` and `<sdk>/ndk-bundle`
+    /// - adb: `<sdk>/platform-tools/adb` and `adb` in `PATH`
+    ///
+    /// A given `sdk` or `ndk` is the only candidate. With `ndk_version`, only an ndk whose
+    /// version starts with it is used, e.g. `25` or `25.1.8937393`. A relative `project_dir`
+    /// is resolved against the working directory.
+    pub fn run(
+        sdk: Option<&Path>,
+        ndk: Option<&Path>,
+        ndk_version: Option<&str>,
+        project_dir: &Path,
+    ) -> Self {
+        let project_dir = env::current_dir().unwrap_or_default().join(project_dir);
+        Self::run_in(sdk, ndk, ndk_version, &project_dir, |name| {
+            env::var_os(name)
+        })
+    }
+
+    fn run_in<F>(
+        sdk: Option<&Path>,
+        ndk: Option<&Path>,
+        ndk_version: Option<&str>,
+        project_dir: &Path,
+        var: F,
+    ) -> Self
+    where
+        F: Fn(&str) -> Option<OsString>,
+    {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+        let local_properties = project_dir
+            .ancestors()
+            .map(|dir| dir.join(LOCAL_PROPERTIES))
+            .find(|path| path.is_file())
+            .and_then(|path| Some((read_properties(&path).ok()?, path)));
+        let local_property = |key: &str| {
+            local_properties.as_ref().and_then(|(properties, path)| {
+                let value = properties.get(key)?;
+                Some((
+                    format!("{} in {}", key, path.display()),
+                    PathBuf::from(unescape(value)),
+                ))
+            })
+        };
+
+        let mut discovery = Self::default();
+
+        let sdk_candidates = match sdk {
+            Some(sdk) => vec![("--android-sdk".to_string(), sdk.to_path_buf())],
+            None => {
+                let mut candidates = vec![];
+                candidates.extend(var_path(var, "ANDROID_HOME"));
+                candidates.extend(var_path(var, "ANDROID_SDK_ROOT"));
+                candidates.extend(local_property("sdk.dir"));
+                let ndk = ndk
+                    .map(Path::to_path_buf)
+                    .or_else(|| var("ANDROID_NDK_ROOT").map(PathBuf::from));
+                candidates.extend(
+                    ndk.as_deref()
+                        .and_then(sdk_of_ndk)
+                        .map(|sdk| ("the sdk of the ndk".to_string(), sdk)),
+                );
+                candidates.extend(default_sdk(var).map(|sdk| ("default".to_string(), sdk)));
+                candidates
+            }
+        };
+        discovery.sdk = discovery.first(sdk_candidates, check_sdk);
+
+        let requested_version = ndk_version
+            .map(|version| NdkVersion::parse(version).ok_or_else(|| version.to_string()));
+        let check_ndk = |path: &Path| check_ndk(path, requested_version.as_ref());
+        let ndk_candidates = match ndk {
+            Some(ndk) => vec![("--android-ndk".to_string(), ndk.to_path_buf())],
+            None => {
+                let mut candidates = vec![];
+                candidates.extend(var_path(var, "ANDROID_NDK_ROOT"));
+                candidates.extend(local_property("ndk.dir"));
+                if let Some(sdk) = &discovery.sdk {
+                    candidates.extend(
+                        ndks_of_sdk(sdk)
+                            .into_iter()
+                            .map(|ndk| ("the sdk".to_string(), ndk)),
+                    );
+                    candidates.push(("the sdk".to_string(), sdk.join("ndk-bundle")));
+                }
+                candidates
+            }
+        };
+        discovery.ndk = discovery.first(ndk_candidates, check_ndk);
+
+        let adb_name = format!("adb{}", env::consts::EXE_SUFFIX);
+        let mut adb_candidates = vec![];
+        if let Some(sdk) = &discovery.sdk {
+            adb_candidates.push((
+                "the sdk".to_string(),
+                sdk.join("platform-tools").join(&adb_name),
+            ));
+        }
+        if let Some(path) = var("PATH") {
+            adb_candidates.extend(
+                env::split_paths(&path).map(|dir| ("PATH".to_string(), dir.join(&adb_name))),
+            );
+        }
+        discovery.adb = discovery.first(adb_candidates, |path| match path.is_file() {
+            true => Ok(()),
+            false => Err("does not exist".to_string()),
+        });
+
+        discovery
+    }
+
+    /// The ndk or an error that lists the paths that were tried.
+    pub fn ndk(&self) -> TaiResult<PathBuf> {
+        self.ndk.clone().ok_or_else(|| {
+            anyhow!(
+                "failed to find the android ndk, set `--android-ndk` or ANDROID_NDK_ROOT{}",
+                self.explain()
+            )
+        })
+    }
+
+    /// `adb` or an error that lists the paths that were tried.
+    pub fn adb(&self) -> TaiResult<PathBuf> {
+        self.adb.clone().ok_or_else(|| {
+            anyhow!(
+                "failed to find adb, set `--android-sdk` or ANDROID_HOME{}",
+                self.explain()
+            )
+        })
+    }
+
+    fn explain(&self) -> String {
+        if self.tried.is_empty() {
+            return String::new();
+        }
+        self.tried
+            .iter()
+            .fold(", tried:".to_string(), |explanation, tried| {
+                format!("{}\n  {}", explanation, tried)
+            })
+    }
+
+    /// The first candidate that passes `check`. The others before it are remembered.
+    fn first<F>(&mut self, candidates: Vec<(String, PathBuf)>, check: F) -> Option<PathBuf>
+    where
+        F: Fn(&Path) -> Result<(), String>,
+    {
+        for (source, path) in candidates {
+            match check(&path) {
+                Ok(()) => return Some(path),
+                Err(reason) => {
+                    self.tried
+                        .push(format!("{}: {} ({})", source, path.display(), reason))
+                }
+            }
+        }
+        None
+    }
+}
+
+fn var_path<F>(var: F, name: &str) -> Option<(String, PathBuf)>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    var(name).map(|value| (name.to_string(), PathBuf::from(value)))
+}
+
+/// The sdk of an ndk in `<sdk>/ndk/<version>` or `<sdk>/ndk-bundle`.
+fn sdk_of_ndk(ndk: &Path) -> Option<PathBuf> {
+    match ndk.file_name() {
+        Some(name) if name == "ndk-bundle" => ndk.parent().map(Into::into),
+        _ => ndk
+            .parent()
+            .filter(|parent| matches!(parent.file_name(), Some(name) if name == "ndk"))
+            .and_then(|parent| parent.parent())
+            .map(Into::into),
+    }
+}
+
+/// The location of the sdk that Android Studio installs by default.
+fn default_sdk<F>(var: F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    if cfg!(windows) {
+        var("LOCALAPPDATA").map(|dir| PathBuf::from(dir).join("Android").join("Sdk"))
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| PathBuf::from(home).join("Library/Android/sdk"))
+    } else {
+        var("HOME").map(|home| PathBuf::from(home).join("Android/Sdk"))
+    }
+}
+
+/// The ndks in `<sdk>/ndk`, the newest first.
+fn ndks_of_sdk(sdk: &Path) -> Vec<PathBuf> {
+    let mut ndks = fs::read_dir(sdk.join("ndk"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| Some((ndk_version(&path).ok()?, path)))
+        .collect::<Vec<_>>();
+    ndks.sort_by(|(a, _), (b, _)| b.cmp(a));
+    ndks.into_iter().map(|(_, path)| path).collect()
+}
+
+fn check_sdk(path: &Path) -> Result<(), String> {
+    if !path.is_dir() {
+        return Err("does not exist".to_string());
+    }
+    match ["platform-tools", "ndk", "ndk-bundle"]
+        .iter()
+        .any(|dir| path.join(dir).is_dir())
+    {
+        true => Ok(()),
+        false => Err("no platform-tools, ndk or ndk-bundle".to_string()),
+    }
+}
+
+fn check_ndk(path: &Path, requested: Option<&Result<NdkVersion, String>>) -> Result<(), String> {
+    if !path.is_dir() {
+        return Err("does not exist".to_string());
+    }
+    let version = ndk_version(path).map_err(|_| "no ndk version in source.properties")?;
+    match requested {
+        None => Ok(()),
+        Some(Ok(requested)) if version.starts_with(requested) => Ok(()),
+        Some(Ok(requested)) => Err(format!("version {} is not {}", version, requested)),
+        Some(Err(requested)) => Err(format!("invalid requested version `{}`", requested)),
+    }
+}
+
+/// Removes the escapes of a value in a `.properties` file, e.g. `C\:\\Android\\sdk`.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn write_ndk(ndk: &Path, revision: &str) {
+        fs::create_dir_all(ndk).unwrap();
+        fs::write(
+            ndk.join("source.properties"),
+            format!("Pkg.Desc = Android NDK\nPkg.Revision = {}\n", revision),
+        )
+        .unwrap();
+    }
+
+    fn write_sdk(sdk: &Path, ndks: &[&str]) {
+        fs::create_dir_all(sdk.join("platform-tools")).unwrap();
+        fs::write(sdk.join("platform-tools").join("adb"), "").unwrap();
+        for ndk in ndks {
+            write_ndk(&sdk.join("ndk").join(ndk), ndk);
+        }
+    }
+
+    fn run_in(
+        dir: &Path,
+        sdk: Option<&Path>,
+        ndk: Option<&Path>,
+        ndk_version: Option<&str>,
+        vars: &[(&str, &Path)],
+    ) -> Discovery {
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.as_os_str().to_owned()))
+            .collect::<HashMap<_, _>>();
+        Discovery::run_in(sdk, ndk, ndk_version, dir, |name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_newest_ndk_of_android_home() {
+        let dir = tempfile::tempdir().unwrap();
+        let sdk = dir.path().join("sdk");
+        write_sdk(&sdk, &["22.1.7171670", "25.1.8937393", "23.2.8568313"]);
+
+        let discovery = run_in(
+            dir.path(),
+            None,
+            None,
+            None,
+            &[
+                ("ANDROID_HOME", &dir.path().join("missing")),
+                ("ANDROID_SDK_ROOT", &sdk),
+            ],
+        );
+        assert_eq!(discovery.sdk, Some(sdk.clone()));
+        assert_eq!(discovery.ndk, Some(sdk.join("ndk/25.1.8937393")));
+        assert_eq!(discovery.adb, Some(sdk.join("platform-tools/adb")));
+
+        let discovery = run_in(
+            dir.path(),
+            None,
+            None,
+            Some("23"),
+            &[("ANDROID_HOME", &sdk)],
+        );
+        assert_eq!(discovery.ndk, Some(sdk.join("ndk/23.2.8568313")));
+
+        let discovery = run_in(
+            dir.path(),
+            None,
+            None,
+            Some("24"),
+            &[("ANDROID_HOME", &sdk)],
+        );
+        assert_eq!(
+            discovery.ndk().unwrap_err().to_string(),
+            format!(
+                "failed to find the android ndk, set `--android-ndk` or ANDROID_NDK_ROOT, tried:\
+                \n  the sdk: {0}/ndk/25.1.8937393 (version 25.1.8937393 is not 24)\
+                \n  the sdk: {0}/ndk/23.2.8568313 (version 23.2.8568313 is not 24)\
+                \n  the sdk: {0}/ndk/22.1.7171670 (version 22.1.7171670 is not 24)\
+                \n  the sdk: {0}/ndk-bundle (does not exist)",
+                sdk.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_sdk_of_ndk_bundle_and_standalone_ndk() {
+        let dir = tempfile::tempdir().unwrap();
+        let sdk = dir.path().join("sdk");
+        write_sdk(&sdk, &[]);
+        write_ndk(&sdk.join("ndk-bundle"), "21.4.7075529");
+        let ndk = dir.path().join("android-ndk-r25b");
+        write_ndk(&ndk, "25.1.8937393");
+
+        let discovery = run_in(dir.path(), None, Some(&sdk.join("ndk-bundle")), None, &[]);
+        assert_eq!(discovery.sdk, Some(sdk.clone()));
+        assert_eq!(discovery.ndk, Some(sdk.join("ndk-bundle")));
+
+        let discovery = run_in(dir.path(), None, None, None, &[("ANDROID_HOME", &sdk)]);
+        assert_eq!(discovery.ndk, Some(sdk.join("ndk-bundle")));
+
+        // a standalone ndk is not part of an sdk
+        let discovery = run_in(
+            dir.path(),
+            None,
+            None,
+            None,
+            &[("ANDROID_NDK_ROOT", &ndk), ("HOME", dir.path())],
+        );
+        assert_eq!(discovery.sdk, None);
+        assert_eq!(discovery.ndk, Some(ndk));
+        assert_eq!(discovery.adb, None);
+        assert!(discovery
+            .adb()
+            .unwrap_err()
+            .to_string()
+            .starts_with("failed to find adb, set `--android-sdk` or ANDROID_HOME, tried:\n"));
+    }
+
+    #[test]
+    fn test_local_properties_and_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let sdk = dir.path().join("sdk");
+        write_sdk(&sdk, &["25.1.8937393"]);
+        let ndk = dir.path().join("ndk");
+        write_ndk(&ndk, "23.2.8568313");
+        let project = dir.path().join("project");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(
+            project.join(LOCAL_PROPERTIES),
+            format!(
+                "sdk.dir={}\nndk.dir={}\n",
+                sdk.display().to_string().replace(':', "\\:"),
+                ndk.display()
+            ),
+        )
+        .unwrap();
+
+        let discovery = run_in(&project.join("src"), None, None, None, &[]);
+        assert_eq!(discovery.sdk, Some(sdk.clone()));
+        assert_eq!(discovery.ndk, Some(ndk));
+
+        // adb of an sdk without platform-tools is looked up in PATH
+        let bin = dir.path().join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        fs::write(bin.join("adb"), "").unwrap();
+        fs::remove_dir_all(sdk.join("platform-tools")).unwrap();
+        let discovery = run_in(dir.path(), Some(&sdk), None, None, &[("PATH", &bin)]);
+        assert_eq!(discovery.ndk, Some(sdk.join("ndk/25.1.8937393")));
+        assert_eq!(discovery.adb, Some(bin.join("adb")));
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r"C\:\\Android\\sdk"), r"C:\Android\sdk");
+        assert_eq!(unescape("/opt/android"), "/opt/android");
+    }
+}
@@ -0,0 +1,361 @@
+//! Starts an Android emulator from an AVD for the run (`--avd`) and shuts it down afterwards.
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context};
+use cfg_expr::targets::Arch;
+use tracing::{info, warn};
+
+use crate::{
+    common::{opts::EmulatorOptions, tools::command_ext::ChildExt},
+    TaiResult,
+};
+
+use super::{
+    adb::{self, CpuArch},
+    read_properties, AndroidEnv,
+};
+
+/// The ports of the emulators, see `emulator -help-port`.
+const FIRST_PORT: u16 = 5554;
+const LAST_PORT: u16 = 5682;
+const BOOT_TIMEOUT: Duration = Duration::from_secs(300);
+const BOOT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const KILL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An emulator that runs the AVD of `--avd`.
+pub struct Emulator {
+    /// The serial of the emulator, e.g. `emulator-5554`
+    pub serial: String,
+    /// The process of the emulator, `None` if it was already running
+    process: Option<Child>,
+}
+
+impl Emulator {
+    /// Starts an emulator for the AVD and waits until it has booted. An emulator that already
+    /// runs the AVD is used as it is.
+    pub fn start(env: &AndroidEnv, opts: &EmulatorOptions, arch: &Arch) -> TaiResult<Self> {
+        let avd_home = avd_home(|name| env::var_os(name).map(PathBuf::from))
+            .ok_or_else(|| anyhow!("failed to find the AVD directory, set ANDROID_AVD_HOME"))?;
+        check_abi(&read_avd_config(&avd_home, &opts.avd)?, &opts.avd, arch)?;
+
+        let serials = adb::serials(env)?;
+        for serial in serials
+            .iter()
+            .filter(|serial| serial.starts_with("emulator-"))
+        {
+            if matches!(adb::emu_avd_name(env, serial), Ok(name) if name == opts.avd) {
+                info!("use running emulator {} of AVD {}", serial, opts.avd);
+                return Ok(Self {
+                    serial: serial.clone(),
+                    process: None,
+                });
+            }
+        }
+
+        let port = free_port(&serials)
+            .ok_or_else(|| anyhow!("no free port for the emulator of AVD {}", opts.avd))?;
+        if env.sdk.as_os_str().is_empty() {
+            bail!("`--avd` requires the android sdk, set `--android-sdk` or ANDROID_HOME");
+        }
+        let emulator = env.sdk.join("emulator").join("emulator");
+        info!("start emulator of AVD {} on port {}", opts.avd, port);
+        let mut cmd = Command::new(&emulator);
+        cmd.args(["-avd", &opts.avd, "-port", &port.to_string()])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if opts.headless {
+            cmd.args(["-no-window", "-no-audio"]);
+        }
+        let process = cmd
+            .spawn()
+            .with_context(|| format!("failed to start {}", emulator.display()))?;
+
+        let mut emulator = Self {
+            serial: format!("emulator-{}", port),
+            process: Some(process),
+        };
+        let booted = match emulator.process.as_mut() {
+            Some(process) => wait_for_boot(
+                env,
+                &emulator.serial,
+                BOOT_TIMEOUT,
+                BOOT_POLL_INTERVAL,
+                || match process.try_wait() {
+                    Ok(Some(status)) => bail!("the emulator exited with {}", status),
+                    _ => Ok(()),
+                },
+            ),
+            None => Ok(()),
+        };
+        if let Err(err) = booted {
+            emulator.kill(env);
+            return Err(err.context(format!("failed to boot AVD {}", opts.avd)));
+        }
+        Ok(emulator)
+    }
+
+    /// Shuts down the emulator if it was started by [`Emulator::start`]. Failures are only
+    /// logged, so that they do not hide the result of the run.
+    pub fn kill(&mut self, env: &AndroidEnv) {
+        let mut process = match self.process.take() {
+            Some(process) => process,
+            None => return,
+        };
+        info!("kill emulator {}", self.serial);
+        if let Err(err) = adb::emu_kill(env, &self.serial) {
+            warn!("{:#}", err);
+        }
+        // `wait_timeout` kills the process if it does not exit in time
+        if let Err(err) = process.wait_timeout(Some(KILL_TIMEOUT)) {
+            warn!("failed to kill emulator {}: {}", self.serial, err);
+        }
+    }
+}
+
+/// Waits until `sys.boot_completed` of the device is `1`. `check` is called before every
+/// poll and stops the waiting with its error, e.g. if the emulator exited.
+pub fn wait_for_boot<F>(
+    env: &AndroidEnv,
+    serial: &str,
+    timeout: Duration,
+    interval: Duration,
+    mut check: F,
+) -> TaiResult<()>
+where
+    F: FnMut() -> TaiResult<()>,
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        check()?;
+        // fails while the device is not yet connected to adb
+        if matches!(adb::getprop(env, serial, "sys.boot_completed"), Ok(value) if value == "1") {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            bail!("{} did not boot within {:?}", serial, timeout);
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// The directory of the AVDs: `ANDROID_AVD_HOME`, `ANDROID_EMULATOR_HOME/avd`,
+/// `ANDROID_USER_HOME/avd` or `~/.android/avd`.
+fn avd_home<F>(var: F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<PathBuf>,
+{
+    var("ANDROID_AVD_HOME")
+        .or_else(|| var("ANDROID_EMULATOR_HOME").map(|home| home.join("avd")))
+        .or_else(|| var("ANDROID_USER_HOME").map(|home| home.join("avd")))
+        .or_else(|| {
+            var("HOME")
+                .or_else(|| var("USERPROFILE"))
+                .map(|home| home.join(".android").join("avd"))
+        })
+}
+
+/// Reads the `config.ini` of the AVD `name` in `avd_home`.
+fn read_avd_config(avd_home: &Path, name: &str) -> TaiResult<HashMap<String, String>> {
+    let ini = avd_home.join(format!("{}.ini", name));
+    if !ini.exists() {
+        bail!(
+            "unknown AVD `{}`, available: {}",
+            name,
+            list_avds(avd_home).join(", ")
+        );
+    }
+    let avd_dir = read_properties(&ini)?
+        .remove("path")
+        .map(PathBuf::from)
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| avd_home.join(format!("{}.avd", name)));
+    read_properties(&avd_dir.join("config.ini"))
+}
+
+/// The names of the AVDs in `avd_home`.
+fn list_avds(avd_home: &Path) -> Vec<String> {
+    let mut avds = fs::read_dir(avd_home)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            match path.extension() {
+                Some(extension) if extension == "ini" => path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string()),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    avds.sort();
+    avds
+}
+
+/// Rejects an AVD whose ABI (`abi.type`) cannot run binaries for `arch`.
+/// The `x86_64` images also run `x86` binaries.
+fn check_abi(config: &HashMap<String, String>, name: &str, arch: &Arch) -> TaiResult<()> {
+    let abi = config
+        .get("abi.type")
+        .ok_or_else(|| anyhow!("the config.ini of AVD `{}` has no `abi.type`", name))?;
+    let required = CpuArch::try_from(*arch)?;
+    let supported = match CpuArch::from(abi.as_str()) {
+        CpuArch::X86_64 => matches!(required, CpuArch::X86_64 | CpuArch::X86),
+        cpu_arch => cpu_arch.as_str() == required.as_str(),
+    };
+    match supported {
+        true => Ok(()),
+        false => bail!(
+            "the AVD `{}` has the ABI `{}`, but the target requires `{}`",
+            name,
+            abi,
+            required.as_str()
+        ),
+    }
+}
+
+/// The first even port whose emulator serial is not taken.
+fn free_port(serials: &[String]) -> Option<u16> {
+    (FIRST_PORT..=LAST_PORT)
+        .step_by(2)
+        .find(|port| !serials.contains(&format!("emulator-{}", port)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_avd(avd_home: &Path, name: &str, abi: &str) {
+        let avd_dir = avd_home.join(format!("{}.avd", name));
+        fs::create_dir_all(&avd_dir).unwrap();
+        fs::write(
+            avd_home.join(format!("{}.ini", name)),
+            format!("avd.ini.encoding=UTF-8\npath={}\n", avd_dir.display()),
+        )
+        .unwrap();
+        fs::write(
+            avd_dir.join("config.ini"),
+            format!("AvdId={}\nabi.type = {}\nhw.cpu.arch=x86_64\n", name, abi),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_avd_home() {
+        let vars = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| PathBuf::from(value))
+            }
+        };
+        assert_eq!(
+            avd_home(vars(&[("ANDROID_AVD_HOME", "/avd"), ("HOME", "/home")])),
+            Some(PathBuf::from("/avd"))
+        );
+        assert_eq!(
+            avd_home(vars(&[("ANDROID_USER_HOME", "/user"), ("HOME", "/home")])),
+            Some(PathBuf::from("/user/avd"))
+        );
+        assert_eq!(
+            avd_home(vars(&[("HOME", "/home")])),
+            Some(PathBuf::from("/home/.android/avd"))
+        );
+        assert_eq!(avd_home(vars(&[])), None);
+    }
+
+    #[test]
+    fn test_read_avd_config_and_check_abi() {
+        let avd_home = tempfile::tempdir().unwrap();
+        write_avd(avd_home.path(), "Pixel_6_API_33", "x86_64");
+        write_avd(avd_home.path(), "Pixel_6_API_33_arm64", "arm64-v8a");
+
+        let config = read_avd_config(avd_home.path(), "Pixel_6_API_33").unwrap();
+        assert!(check_abi(&config, "Pixel_6_API_33", &Arch::x86_64).is_ok());
+        assert!(check_abi(&config, "Pixel_6_API_33", &Arch::x86).is_ok());
+        assert_eq!(
+            check_abi(&config, "Pixel_6_API_33", &Arch::aarch64)
+                .unwrap_err()
+                .to_string(),
+            "the AVD `Pixel_6_API_33` has the ABI `x86_64`, but the target requires `arm64-v8a`"
+        );
+
+        let config = read_avd_config(avd_home.path(), "Pixel_6_API_33_arm64").unwrap();
+        assert!(check_abi(&config, "Pixel_6_API_33_arm64", &Arch::aarch64).is_ok());
+        assert!(check_abi(&config, "Pixel_6_API_33_arm64", &Arch::arm).is_err());
+
+        assert_eq!(
+            read_avd_config(avd_home.path(), "Nexus")
+                .unwrap_err()
+                .to_string(),
+            "unknown AVD `Nexus`, available: Pixel_6_API_33, Pixel_6_API_33_arm64"
+        );
+    }
+
+    #[test]
+    fn test_free_port() {
+        assert_eq!(free_port(&[]), Some(5554));
+        assert_eq!(
+            free_port(&["emulator-5554".to_string(), "R5CT".to_string()]),
+            Some(5556)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_for_boot() {
+        use crate::android::tools::fake_adb::FakeAdb;
+
+        // the device boots on the third poll
+        let adb = FakeAdb::new(
+            r#"
+polls="$(dirname "$0")/polls"
+echo x >> "$polls"
+if [ "$(wc -l < "$polls")" -ge 3 ]; then echo 1; fi
+exit 0
+"#,
+        );
+        let mut checks = 0;
+        wait_for_boot(
+            adb.env(),
+            "emulator-5554",
+            Duration::from_secs(10),
+            Duration::from_millis(10),
+            || {
+                checks += 1;
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(checks, 3);
+
+        let adb = FakeAdb::new("exit 1");
+        let err = wait_for_boot(
+            adb.env(),
+            "emulator-5554",
+            Duration::from_millis(50),
+            Duration::from_millis(10),
+            || Ok(()),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("did not boot"));
+
+        let err = wait_for_boot(
+            adb.env(),
+            "emulator-5554",
+            Duration::from_secs(10),
+            Duration::from_millis(10),
+            || bail!("the emulator exited"),
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "the emulator exited");
+    }
+}
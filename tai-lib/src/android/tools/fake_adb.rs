@@ -0,0 +1,41 @@
+//! A fake `adb` for tests that is backed by a shell script.
+use std::{
+    fs::{self, Permissions},
+    os::unix::fs::PermissionsExt,
+    path::Path,
+};
+
+use tempfile::TempDir;
+
+use super::AndroidEnv;
+
+pub struct FakeAdb {
+    dir: TempDir,
+    env: AndroidEnv,
+}
+
+impl FakeAdb {
+    /// Creates an `adb` executable that runs `script` with the arguments
+    /// it was called with, e.g. `$1=-s $2=<serial> $3=shell $4=<command>`.
+    pub fn new(script: &str) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let adb = dir.path().join("adb");
+        fs::write(&adb, format!("#!/bin/sh\n{}", script)).unwrap();
+        fs::set_permissions(&adb, Permissions::from_mode(0o755)).unwrap();
+
+        let env = AndroidEnv {
+            adb,
+            ndk: dir.path().join("ndk"),
+            sdk: dir.path().to_path_buf(),
+        };
+        Self { dir, env }
+    }
+
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn env(&self) -> &AndroidEnv {
+        &self.env
+    }
+}
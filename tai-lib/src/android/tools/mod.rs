@@ -1,11 +1,24 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use anyhow::anyhow;
+use anyhow::Context;
+use tracing::debug;
 
 use crate::{common::opts::AndroidOptions, TaiResult};
 
+use self::discovery::Discovery;
+
 pub mod adb;
+pub mod discovery;
+pub mod emulator;
+#[cfg(all(test, unix))]
+pub mod fake_adb;
+pub mod ndk;
 
+#[derive(Debug, Clone)]
 pub struct AndroidEnv {
     pub adb: PathBuf,
     pub ndk: PathBuf,
@@ -13,15 +26,46 @@ pub struct AndroidEnv {
 }
 
 impl AndroidEnv {
-    pub fn derive_env(opts: &AndroidOptions) -> TaiResult<AndroidEnv> {
-        let ndk = opts.ndk.to_path_buf();
-        let sdk = opts
-            .sdk
-            .clone()
-            .or_else(|| ndk.parent().and_then(|p| p.parent()).map(Into::into))
-            .ok_or_else(|| anyhow!("failed to find `sdk` folder in ../../{}", ndk.display()))?;
-
-        let adb = sdk.join("platform-tools").join("adb");
-        Ok(Self { adb, ndk, sdk })
+    /// Discovers the sdk, the ndk and `adb` for the crate in `project_dir`, see
+    /// [`Discovery::run`]. The sdk is empty if only a standalone ndk and the `adb` in `PATH`
+    /// were found.
+    pub fn derive_env(opts: &AndroidOptions, project_dir: &Path) -> TaiResult<AndroidEnv> {
+        let discovery = Discovery::run(
+            opts.sdk.as_deref(),
+            opts.ndk.as_deref(),
+            opts.ndk_version.as_deref(),
+            project_dir,
+        );
+        let ndk = discovery.ndk()?;
+        let adb = discovery.adb()?;
+        debug!("sdk: {:?}, ndk: {}", discovery.sdk, ndk.display());
+        Ok(Self {
+            adb,
+            ndk,
+            sdk: discovery.sdk.unwrap_or_default(),
+        })
+    }
+
+    /// An environment that can only be used to run `adb`, e.g. to list the devices.
+    /// Falls back to `adb` if it was not discovered.
+    pub fn adb_only(sdk: Option<PathBuf>, ndk: Option<PathBuf>, project_dir: &Path) -> AndroidEnv {
+        let discovery = Discovery::run(sdk.as_deref(), ndk.as_deref(), None, project_dir);
+        Self {
+            adb: discovery.adb.unwrap_or_else(|| PathBuf::from("adb")),
+            ndk: discovery.ndk.unwrap_or_default(),
+            sdk: discovery.sdk.unwrap_or_default(),
+        }
     }
 }
+
+/// Reads the `key=value` lines of an `.ini` or `.properties` file of the sdk, e.g. the
+/// `source.properties` of the ndk.
+fn read_properties(path: &Path) -> TaiResult<HashMap<String, String>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect())
+}
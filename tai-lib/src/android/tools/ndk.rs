@@ -0,0 +1,432 @@
+//! Configures cargo to build with the clang toolchain of the ndk.
+use std::{
+    ffi::OsString,
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context};
+use cfg_expr::targets::{Arch, TargetInfo};
+
+use crate::TaiResult;
+
+use super::{adb::quote, read_properties};
+
+/// The tag of the prebuilt toolchain that runs on this host. The ndk only ships
+/// `x86_64` toolchains, which also run on Apple silicon.
+pub const HOST_TAG: &str = if cfg!(target_os = "macos") {
+    "darwin-x86_64"
+} else if cfg!(windows) {
+    "windows-x86_64"
+} else {
+    "linux-x86_64"
+};
+
+/// The first ndk whose prebuilt toolchain contains the clang wrappers of each API level.
+const MIN_NDK_MAJOR: u32 = 19;
+/// The first ndk that ships `llvm-ar` instead of the GNU binutils.
+const LLVM_AR_NDK_MAJOR: u32 = 23;
+/// The first ndk without `libgcc`, which Rust before 1.68 still links with `-lgcc`.
+const NO_LIBGCC_NDK_MAJOR: u32 = 23;
+const LIBGCC_SCRIPT: &str = "libgcc.a";
+
+/// The version of an ndk, e.g. `25.1.8937393`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NdkVersion(Vec<u32>);
+
+impl NdkVersion {
+    /// Parses the leading digits of each component, e.g. `26.0.10404224-beta1`.
+    pub fn parse(version: &str) -> Option<Self> {
+        version
+            .split('.')
+            .map(|part| {
+                let digits = part
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(part.len());
+                part[..digits].parse().ok()
+            })
+            .collect::<Option<Vec<u32>>>()
+            .map(Self)
+    }
+
+    /// Whether `prefix` selects this version, e.g. `25` or `25.1` for `25.1.8937393`.
+    pub fn starts_with(&self, prefix: &NdkVersion) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+
+    pub fn major(&self) -> u32 {
+        self.0.first().copied().unwrap_or_default()
+    }
+}
+
+impl Display for NdkVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = self.0.iter().map(u32::to_string).collect::<Vec<_>>();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+/// Reads the version of the ndk from `Pkg.Revision` of its `source.properties`.
+pub fn ndk_version(ndk: &Path) -> TaiResult<NdkVersion> {
+    let path = ndk.join("source.properties");
+    let revision = read_properties(&path)?
+        .remove("Pkg.Revision")
+        .ok_or_else(|| anyhow!("no `Pkg.Revision` in {}", path.display()))?;
+    NdkVersion::parse(&revision)
+        .ok_or_else(|| anyhow!("invalid ndk version `{}` in {}", revision, path.display()))
+}
+
+/// The environment of cargo for the clang toolchain of an ndk.
+#[derive(Debug)]
+pub struct Toolchain {
+    pub env: Vec<(String, OsString)>,
+    /// The linker that `env` refers to, which must be written before cargo runs.
+    pub linker_wrapper: Option<LinkerWrapper>,
+}
+
+/// A script that runs the clang of the ndk with its directory as additional library search
+/// path. The directory also contains a `libgcc.a` linker script that links `libunwind`
+/// instead, like cargo-ndk does for the ndks without `libgcc`.
+///
+/// Unlike `-L` in `CARGO_TARGET_<TRIPLE>_RUSTFLAGS`, the wrapper keeps the rustflags of the
+/// user and of `.cargo/config.toml` in effect.
+#[derive(Debug)]
+pub struct LinkerWrapper {
+    pub dir: PathBuf,
+    pub path: PathBuf,
+    pub script: String,
+}
+
+impl LinkerWrapper {
+    fn new(dir: &Path, cc: &Path, windows: bool) -> TaiResult<Self> {
+        let path = dir.join(cc.file_name().ok_or_else(|| anyhow!("invalid linker"))?);
+        let utf8 = |path: &Path| -> TaiResult<String> {
+            path.to_str()
+                .map(str::to_owned)
+                .ok_or_else(|| anyhow!("{} is not valid unicode", path.display()))
+        };
+        let (cc, dir_arg) = (utf8(cc)?, utf8(dir)?);
+        let script = match windows {
+            true => format!("@echo off\r\n\"{}\" -L \"{}\" %*\r\n", cc, dir_arg),
+            false => format!(
+                "#!/bin/sh\nexec {} -L {} \"$@\"\n",
+                quote(&cc),
+                quote(&dir_arg)
+            ),
+        };
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            path,
+            script,
+        })
+    }
+
+    /// Writes the wrapper and the `libgcc.a` linker script.
+    pub fn write(&self) -> TaiResult<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let libgcc = self.dir.join(LIBGCC_SCRIPT);
+        fs::write(&libgcc, "INPUT(-lunwind)\n")
+            .with_context(|| format!("Failed to write {}", libgcc.display()))?;
+        fs::write(&self.path, &self.script)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&self.path, fs::Permissions::from_mode(0o755))
+                .with_context(|| format!("Failed to make {} executable", self.path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the environment variables that make `cargo build --target <triple>` link with the
+/// clang of the ndk for `api_lvl`, and that point the `cc` and `bindgen` crates to the
+/// compilers, the archiver and the sysroot of the ndk.
+///
+/// For ndks without `libgcc`, cargo links with a [`LinkerWrapper`] in `linker_dir` instead.
+pub fn toolchain_env(
+    ndk: &Path,
+    host_tag: &str,
+    target: &TargetInfo,
+    api_lvl: u8,
+    linker_dir: &Path,
+) -> TaiResult<Toolchain> {
+    let version = ndk_version(ndk)?;
+    if version.major() < MIN_NDK_MAJOR {
+        bail!(
+            "the ndk {} in {} is not supported, use ndk {} or newer",
+            version,
+            ndk.display(),
+            MIN_NDK_MAJOR
+        );
+    }
+
+    let toolchain = ndk
+        .join("toolchains")
+        .join("llvm")
+        .join("prebuilt")
+        .join(host_tag);
+    if !toolchain.exists() {
+        bail!("failed to find the llvm toolchain {}", toolchain.display());
+    }
+    let bin = toolchain.join("bin");
+
+    let (clang_target, binutils_prefix) = match target.arch {
+        Arch::aarch64 => ("aarch64-linux-android", "aarch64-linux-android"),
+        Arch::arm => ("armv7a-linux-androideabi", "arm-linux-androideabi"),
+        Arch::x86 => ("i686-linux-android", "i686-linux-android"),
+        Arch::x86_64 => ("x86_64-linux-android", "x86_64-linux-android"),
+        _ => bail!("{} is not an android target", target.triple),
+    };
+    let windows = host_tag.starts_with("windows");
+    let (script_ext, exe_ext) = match windows {
+        true => (".cmd", ".exe"),
+        false => ("", ""),
+    };
+
+    let clang = |suffix: &str| -> PathBuf {
+        bin.join(format!(
+            "{}{}-{}{}",
+            clang_target, api_lvl, suffix, script_ext
+        ))
+    };
+    let cc = clang("clang");
+    if !cc.exists() {
+        bail!(
+            "the ndk {} does not support the android API level {}, failed to find {}",
+            version,
+            api_lvl,
+            cc.display()
+        );
+    }
+    let cxx = clang("clang++");
+    let ar = match version.major() >= LLVM_AR_NDK_MAJOR {
+        true => bin.join(format!("llvm-ar{}", exe_ext)),
+        false => bin.join(format!("{}-ar{}", binutils_prefix, exe_ext)),
+    };
+    let mut sysroot = OsString::from("--sysroot=");
+    sysroot.push(toolchain.join("sysroot"));
+
+    let linker_wrapper = match version.major() >= NO_LIBGCC_NDK_MAJOR {
+        true => Some(LinkerWrapper::new(linker_dir, &cc, windows)?),
+        false => None,
+    };
+    let linker = linker_wrapper
+        .as_ref()
+        .map_or_else(|| cc.clone(), |wrapper| wrapper.path.clone());
+
+    let triple = target.triple.replace('-', "_");
+    let env = vec![
+        (
+            format!("CARGO_TARGET_{}_LINKER", triple.to_uppercase()),
+            linker.into(),
+        ),
+        (format!("CC_{}", triple), cc.into()),
+        (format!("CXX_{}", triple), cxx.into()),
+        (format!("AR_{}", triple), ar.into()),
+        (format!("BINDGEN_EXTRA_CLANG_ARGS_{}", triple), sysroot),
+    ];
+    Ok(Toolchain {
+        env,
+        linker_wrapper,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use cfg_expr::targets::get_builtin_target_by_triple;
+
+    use super::*;
+
+    /// Creates the parts of an ndk that `toolchain_env` looks at.
+    fn fake_ndk(ndk: &Path, revision: &str, host_tag: &str, files: &[&str]) -> PathBuf {
+        fs::write(
+            ndk.join("source.properties"),
+            format!("Pkg.Desc = Android NDK\nPkg.Revision = {}\n", revision),
+        )
+        .unwrap();
+        let toolchain = ndk.join("toolchains/llvm/prebuilt").join(host_tag);
+        fs::create_dir_all(toolchain.join("bin")).unwrap();
+        fs::create_dir_all(toolchain.join("sysroot")).unwrap();
+        for file in files {
+            fs::write(toolchain.join("bin").join(file), "").unwrap();
+        }
+        toolchain
+    }
+
+    fn env_of(
+        ndk: &Path,
+        host_tag: &str,
+        triple: &str,
+        api_lvl: u8,
+    ) -> TaiResult<HashMap<String, OsString>> {
+        let target = get_builtin_target_by_triple(triple).unwrap();
+        Ok(
+            toolchain_env(ndk, host_tag, target, api_lvl, &ndk.join("linker"))?
+                .env
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_ndk_version() {
+        let version = NdkVersion::parse("25.1.8937393").unwrap();
+        assert_eq!(version.major(), 25);
+        assert_eq!(version.to_string(), "25.1.8937393");
+        assert_eq!(
+            NdkVersion::parse("26.0.10404224-beta1").unwrap(),
+            NdkVersion(vec![26, 0, 10404224])
+        );
+        assert!(NdkVersion::parse("22.1.7171670").unwrap() < version);
+        assert!(NdkVersion::parse("r22").is_none());
+    }
+
+    #[test]
+    fn test_toolchain_env() {
+        let ndk = tempfile::tempdir().unwrap();
+        let toolchain = fake_ndk(
+            ndk.path(),
+            "25.1.8937393",
+            "linux-x86_64",
+            &["aarch64-linux-android21-clang", "llvm-ar"],
+        );
+        let bin = toolchain.join("bin");
+
+        let env = env_of(ndk.path(), "linux-x86_64", "aarch64-linux-android", 21).unwrap();
+        let cc = OsString::from(bin.join("aarch64-linux-android21-clang"));
+        assert_eq!(env.len(), 5);
+        assert_eq!(
+            env["CARGO_TARGET_AARCH64_LINUX_ANDROID_LINKER"],
+            OsString::from(ndk.path().join("linker/aarch64-linux-android21-clang"))
+        );
+        assert_eq!(env["CC_aarch64_linux_android"], cc);
+        assert_eq!(
+            env["CXX_aarch64_linux_android"],
+            OsString::from(bin.join("aarch64-linux-android21-clang++"))
+        );
+        assert_eq!(
+            env["AR_aarch64_linux_android"],
+            OsString::from(bin.join("llvm-ar"))
+        );
+        let mut sysroot = OsString::from("--sysroot=");
+        sysroot.push(toolchain.join("sysroot"));
+        assert_eq!(
+            env["BINDGEN_EXTRA_CLANG_ARGS_aarch64_linux_android"],
+            sysroot
+        );
+
+        let err = env_of(ndk.path(), "linux-x86_64", "aarch64-linux-android", 16).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("the ndk 25.1.8937393 does not support the android API level 16"));
+        assert!(env_of(ndk.path(), "darwin-x86_64", "aarch64-linux-android", 21).is_err());
+    }
+
+    #[test]
+    fn test_toolchain_env_of_older_ndks() {
+        let ndk = tempfile::tempdir().unwrap();
+        let toolchain = fake_ndk(
+            ndk.path(),
+            "22.1.7171670",
+            "windows-x86_64",
+            &["armv7a-linux-androideabi21-clang.cmd"],
+        );
+        let bin = toolchain.join("bin");
+
+        let env = env_of(ndk.path(), "windows-x86_64", "armv7-linux-androideabi", 21).unwrap();
+        assert_eq!(
+            env["CARGO_TARGET_ARMV7_LINUX_ANDROIDEABI_LINKER"],
+            OsString::from(bin.join("armv7a-linux-androideabi21-clang.cmd"))
+        );
+        assert_eq!(
+            env["AR_armv7_linux_androideabi"],
+            OsString::from(bin.join("arm-linux-androideabi-ar.exe"))
+        );
+        assert_eq!(env.len(), 5);
+
+        fake_ndk(ndk.path(), "18.1.5063045", "windows-x86_64", &[]);
+        let err = env_of(ndk.path(), "windows-x86_64", "armv7-linux-androideabi", 21).unwrap_err();
+        assert!(err.to_string().contains("use ndk 19 or newer"));
+    }
+
+    #[test]
+    fn test_linker_wrapper() {
+        let ndk = tempfile::tempdir().unwrap();
+        let toolchain = fake_ndk(
+            ndk.path(),
+            "25.1.8937393",
+            "windows-x86_64",
+            &["x86_64-linux-android21-clang.cmd"],
+        );
+        let target = get_builtin_target_by_triple("x86_64-linux-android").unwrap();
+        let linker_dir = ndk.path().join("target dir");
+        let wrapper = toolchain_env(ndk.path(), "windows-x86_64", target, 21, &linker_dir)
+            .unwrap()
+            .linker_wrapper
+            .unwrap();
+        assert_eq!(
+            wrapper.path,
+            linker_dir.join("x86_64-linux-android21-clang.cmd")
+        );
+        assert_eq!(
+            wrapper.script,
+            format!(
+                "@echo off\r\n\"{}\" -L \"{}\" %*\r\n",
+                toolchain
+                    .join("bin/x86_64-linux-android21-clang.cmd")
+                    .display(),
+                linker_dir.display()
+            )
+        );
+    }
+
+    /// The wrapper passes the library search path to the linker without touching the
+    /// rustflags, so a user-set `CARGO_TARGET_<TRIPLE>_RUSTFLAGS` stays in effect.
+    #[cfg(unix)]
+    #[test]
+    fn test_linker_wrapper_keeps_rustflags() {
+        use std::process::Command;
+
+        let ndk = tempfile::tempdir().unwrap();
+        let toolchain = fake_ndk(ndk.path(), "25.1.8937393", "linux-x86_64", &[]);
+        let cc = toolchain.join("bin/aarch64-linux-android21-clang");
+        fs::write(&cc, "#!/bin/sh\necho \"$@\"\n").unwrap();
+        fs::set_permissions(&cc, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+
+        let target = get_builtin_target_by_triple("aarch64-linux-android").unwrap();
+        let linker_dir = ndk.path().join("user's target/ndk-linker");
+        let toolchain = toolchain_env(ndk.path(), "linux-x86_64", target, 21, &linker_dir).unwrap();
+        let wrapper = toolchain.linker_wrapper.unwrap();
+        wrapper.write().unwrap();
+        assert_eq!(
+            fs::read_to_string(linker_dir.join("libgcc.a")).unwrap(),
+            "INPUT(-lunwind)\n"
+        );
+
+        let mut cmd = Command::new("cargo");
+        cmd.env(
+            "CARGO_TARGET_AARCH64_LINUX_ANDROID_RUSTFLAGS",
+            "-C link-arg=-Wl,--gc-sections",
+        );
+        cmd.envs(toolchain.env);
+        assert!(cmd
+            .get_envs()
+            .filter(|(key, _)| key.to_string_lossy().ends_with("RUSTFLAGS"))
+            .all(|(_, value)| value == Some("-C link-arg=-Wl,--gc-sections".as_ref())));
+
+        let output = Command::new(&wrapper.path)
+            .args(["-o", "out"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("-L {} -o out\n", linker_dir.display())
+        );
+    }
+}
@@ -0,0 +1,417 @@
+//! Criterion baselines of the benchmarks that ran on the devices.
+//!
+//! After a `bench`/`benches` run, the `estimates.json` files that criterion wrote on the devices
+//! are read from the copied artifacts (`--artifacts`). They can be saved as a baseline in
+//! `target/cargo-tai/baselines/<device>/<name>` and compared with an earlier baseline.
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use tracing::warn;
+use walkdir::WalkDir;
+
+use crate::{
+    common::{
+        opts::BaselineOptions,
+        report::{sanitize_file_name, Reporter, RunReport},
+        table::write_table,
+    },
+    TaiResult,
+};
+
+/// The directory in the cargo-tai target directory in which the baselines are saved.
+pub const BASELINES_DIR: &str = "baselines";
+const ESTIMATES_FILE: &str = "estimates.json";
+/// The directory of the artifacts into which `tai_util::criterion` writes the reports.
+const CRITERION_DIR: &str = "criterion";
+
+/// The estimates of a benchmark.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Benchmark {
+    /// The `estimates.json` of criterion
+    pub path: PathBuf,
+    /// The mean time in nanoseconds
+    pub mean: f64,
+}
+
+/// The benchmarks by their criterion id, e.g. `fib 20` or `group/function`.
+pub type Benchmarks = BTreeMap<String, Benchmark>;
+
+#[derive(Deserialize)]
+struct EstimatesFile {
+    mean: Estimate,
+}
+
+#[derive(Deserialize)]
+struct Estimate {
+    point_estimate: f64,
+}
+
+/// Saves and/or compares the estimates of the benchmarks of `report`.
+///
+/// The comparison is printed as a table. Fails if a benchmark is slower than the baseline by more
+/// than the threshold.
+pub fn process(
+    opts: &BaselineOptions,
+    report: &RunReport,
+    reporter: &Reporter,
+    tai_target: &Path,
+) -> TaiResult<()> {
+    let mut bundles: BTreeMap<&str, Vec<(&str, Benchmarks)>> = BTreeMap::new();
+    for result in &report.results {
+        if let Some(dir) = reporter.artifacts_dir(&result.device, &result.bundle) {
+            bundles
+                .entry(&result.device)
+                .or_default()
+                .push((&result.bundle, read_criterion_benchmarks(&dir)?));
+        }
+    }
+    let current = bundles
+        .into_iter()
+        .map(|(device, bundles)| Ok((device, merge_benchmarks(bundles)?)))
+        .collect::<TaiResult<BTreeMap<_, _>>>()?;
+    if current.values().all(Benchmarks::is_empty) {
+        warn!("no criterion estimates found, see `tai_util::criterion_main_tai!`");
+        return Ok(());
+    }
+
+    let baselines_root = tai_target.join(BASELINES_DIR);
+    let mut regressions = 0;
+    if let Some(name) = &opts.compare {
+        let mut comparisons = Vec::new();
+        for (device, benchmarks) in &current {
+            let baseline = read_baseline(&baselines_root.join(sanitize_file_name(device)), name)?;
+            if baseline.is_empty() {
+                warn!("no baseline {} for device {}", name, device);
+            }
+            comparisons.extend(compare(device, &baseline, benchmarks));
+        }
+        regressions = comparisons
+            .iter()
+            .filter(|comparison| comparison.regressed(opts.threshold))
+            .count();
+        let table = Comparisons {
+            comparisons,
+            threshold: opts.threshold,
+        };
+        let _ = write!(reporter.human_output(), "\n{}", table);
+    }
+
+    if let Some(name) = &opts.save {
+        for (device, benchmarks) in &current {
+            save_baseline(
+                &baselines_root.join(sanitize_file_name(device)),
+                name,
+                benchmarks,
+            )?;
+        }
+    }
+
+    if regressions > 0 {
+        bail!(
+            "{} benchmark(s) are more than {}% slower than the baseline {}",
+            regressions,
+            opts.threshold,
+            opts.compare.as_deref().unwrap_or_default()
+        )
+    }
+    Ok(())
+}
+
+/// Merges the benchmarks of the bundles of a device. Fails if two bundles use the same id,
+/// because a baseline only knows the ids.
+fn merge_benchmarks(bundles: Vec<(&str, Benchmarks)>) -> TaiResult<Benchmarks> {
+    let mut merged = Benchmarks::new();
+    let mut owners = BTreeMap::new();
+    for (bundle, benchmarks) in bundles {
+        for (id, benchmark) in benchmarks {
+            if let Some(other) = owners.insert(id.clone(), bundle) {
+                bail!(
+                    "the benchmark `{}` is defined in the bundles {} and {}, \
+                    use distinct ids to compare them with a baseline",
+                    id,
+                    other,
+                    bundle
+                );
+            }
+            merged.insert(id, benchmark);
+        }
+    }
+    Ok(merged)
+}
+
+/// Reads the estimates of the latest run that criterion wrote into `dir` or into `dir/criterion`.
+pub fn read_criterion_benchmarks(dir: &Path) -> TaiResult<Benchmarks> {
+    let criterion_dir = dir.join(CRITERION_DIR);
+    let root = match criterion_dir.is_dir() {
+        true => criterion_dir,
+        false => dir.to_path_buf(),
+    };
+    read_benchmarks(&root, |dir| dir.strip_suffix("/new"))
+}
+
+/// Reads the baseline `name` of a device.
+pub fn read_baseline(device_dir: &Path, name: &str) -> TaiResult<Benchmarks> {
+    read_benchmarks(&device_dir.join(name), |dir| Some(dir))
+}
+
+/// Saves the benchmarks as baseline `name` of a device. An earlier baseline with this name is
+/// replaced.
+pub fn save_baseline(device_dir: &Path, name: &str, benchmarks: &Benchmarks) -> TaiResult<()> {
+    let baseline_dir = device_dir.join(name);
+    if baseline_dir.exists() {
+        fs::remove_dir_all(&baseline_dir)
+            .with_context(|| format!("Failed to remove {}", baseline_dir.display()))?;
+    }
+    for (id, benchmark) in benchmarks {
+        let dir = id
+            .split('/')
+            .fold(baseline_dir.clone(), |dir, c| dir.join(c));
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        fs::copy(&benchmark.path, dir.join(ESTIMATES_FILE))
+            .with_context(|| format!("Failed to copy {}", benchmark.path.display()))?;
+    }
+    Ok(())
+}
+
+/// Reads all `estimates.json` in `root`. `id` returns the id of a benchmark for the
+/// `/`-separated directory of an `estimates.json` or `None` if the file is not an estimate.
+fn read_benchmarks<F>(root: &Path, id: F) -> TaiResult<Benchmarks>
+where
+    F: Fn(&str) -> Option<&str>,
+{
+    let mut benchmarks = Benchmarks::new();
+    if !root.is_dir() {
+        return Ok(benchmarks);
+    }
+
+    for entry in WalkDir::new(root) {
+        let entry = entry.with_context(|| format!("Failed to read {}", root.display()))?;
+        if entry.file_name() != ESTIMATES_FILE {
+            continue;
+        }
+        let dir = match entry.path().parent().map(|dir| dir.strip_prefix(root)) {
+            Some(Ok(dir)) => dir
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            _ => continue,
+        };
+        if let Some(id) = id(&dir) {
+            let content = fs::read_to_string(entry.path())
+                .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+            let estimates: EstimatesFile = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", entry.path().display()))?;
+            benchmarks.insert(
+                id.to_string(),
+                Benchmark {
+                    path: entry.path().to_path_buf(),
+                    mean: estimates.mean.point_estimate,
+                },
+            );
+        }
+    }
+    Ok(benchmarks)
+}
+
+/// A benchmark of a run and its baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub device: String,
+    pub benchmark: String,
+    /// The mean time of the baseline in nanoseconds, `None` for a new benchmark
+    pub baseline: Option<f64>,
+    /// The mean time of the run in nanoseconds
+    pub current: f64,
+}
+
+impl Comparison {
+    /// The change of the mean time in percent.
+    pub fn change(&self) -> Option<f64> {
+        self.baseline
+            .map(|baseline| (self.current - baseline) / baseline * 100.0)
+    }
+
+    pub fn regressed(&self, threshold: f64) -> bool {
+        matches!(self.change(), Some(change) if change > threshold)
+    }
+}
+
+pub fn compare(device: &str, baseline: &Benchmarks, current: &Benchmarks) -> Vec<Comparison> {
+    current
+        .iter()
+        .map(|(id, benchmark)| Comparison {
+            device: device.to_string(),
+            benchmark: id.clone(),
+            baseline: baseline.get(id).map(|baseline| baseline.mean),
+            current: benchmark.mean,
+        })
+        .collect()
+}
+
+/// The comparisons, printed as a table.
+pub struct Comparisons {
+    pub comparisons: Vec<Comparison>,
+    /// Regression threshold in percent
+    pub threshold: f64,
+}
+
+impl Display for Comparisons {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        const HEADER: [&str; 5] = ["device", "benchmark", "baseline", "current", "change"];
+
+        let rows = self
+            .comparisons
+            .iter()
+            .map(|comparison| {
+                vec![
+                    comparison.device.clone(),
+                    comparison.benchmark.clone(),
+                    comparison.baseline.map(format_time).unwrap_or_default(),
+                    format_time(comparison.current),
+                    match comparison.change() {
+                        Some(change) if comparison.regressed(self.threshold) => {
+                            format!("{:+.2}% (regressed)", change)
+                        }
+                        Some(change) => format!("{:+.2}%", change),
+                        None => "new".to_string(),
+                    },
+                ]
+            })
+            .collect::<Vec<_>>();
+        write_table(fmt, &HEADER, &rows)
+    }
+}
+
+/// Formats nanoseconds like criterion.
+fn format_time(ns: f64) -> String {
+    if ns < 1e3 {
+        format!("{:.2} ns", ns)
+    } else if ns < 1e6 {
+        format!("{:.2} µs", ns / 1e3)
+    } else if ns < 1e9 {
+        format!("{:.2} ms", ns / 1e6)
+    } else {
+        format!("{:.2} s", ns / 1e9)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_estimates(path: &Path, mean: f64) {
+        fs::create_dir_all(path).unwrap();
+        fs::write(
+            path.join(ESTIMATES_FILE),
+            format!(
+                r#"{{"mean":{{"confidence_interval":{{"confidence_level":0.95,"lower_bound":{0},"upper_bound":{0}}},"point_estimate":{0},"standard_error":0.1}}}}"#,
+                mean
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_read_and_save_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        let artifacts = dir.path().join("artifacts");
+        write_estimates(&artifacts.join("criterion/fib 20/new"), 10.0);
+        write_estimates(&artifacts.join("criterion/fib 20/base"), 12.0);
+        write_estimates(&artifacts.join("criterion/group/sort/new"), 2e6);
+
+        let benchmarks = read_criterion_benchmarks(&artifacts).unwrap();
+        assert_eq!(
+            benchmarks
+                .iter()
+                .map(|(id, benchmark)| (id.as_str(), benchmark.mean))
+                .collect::<Vec<_>>(),
+            vec![("fib 20", 10.0), ("group/sort", 2e6)]
+        );
+
+        let device_dir = dir.path().join("baselines/emulator-5554");
+        save_baseline(&device_dir, "main", &benchmarks).unwrap();
+        let baseline = read_baseline(&device_dir, "main").unwrap();
+        assert_eq!(
+            baseline.keys().collect::<Vec<_>>(),
+            vec!["fib 20", "group/sort"]
+        );
+        assert_eq!(baseline["group/sort"].mean, 2e6);
+        assert!(read_baseline(&device_dir, "unknown").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_merge_benchmarks() {
+        let benchmarks = |ids: &[&str]| {
+            ids.iter()
+                .map(|id| {
+                    let benchmark = Benchmark {
+                        path: PathBuf::new(),
+                        mean: 1.0,
+                    };
+                    (id.to_string(), benchmark)
+                })
+                .collect::<Benchmarks>()
+        };
+
+        let merged = merge_benchmarks(vec![
+            ("a", benchmarks(&["fib 20"])),
+            ("b", benchmarks(&["fib 30"])),
+        ])
+        .unwrap();
+        assert_eq!(merged.keys().collect::<Vec<_>>(), vec!["fib 20", "fib 30"]);
+
+        let err = merge_benchmarks(vec![
+            ("a", benchmarks(&["fib 20"])),
+            ("b", benchmarks(&["fib 20"])),
+        ])
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("the benchmark `fib 20` is defined in the bundles a and b"));
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let benchmark = |mean| Benchmark {
+            path: PathBuf::new(),
+            mean,
+        };
+        let baseline = Benchmarks::from([
+            ("fib 20".to_string(), benchmark(10.0)),
+            ("group/sort".to_string(), benchmark(2e6)),
+        ]);
+        let current = Benchmarks::from([
+            ("fib 20".to_string(), benchmark(11.0)),
+            ("group/sort".to_string(), benchmark(1.9e6)),
+            ("new".to_string(), benchmark(1.5e3)),
+        ]);
+
+        let comparisons = compare("emulator-5554", &baseline, &current);
+        assert!(comparisons[0].regressed(5.0));
+        assert!(!comparisons[0].regressed(10.0));
+        assert!(!comparisons[1].regressed(5.0));
+        assert!(!comparisons[2].regressed(5.0));
+
+        assert_eq!(
+            Comparisons {
+                comparisons,
+                threshold: 5.0
+            }
+            .to_string(),
+            "\
+device         benchmark   baseline  current   change
+emulator-5554  fib 20      10.00 ns  11.00 ns  +10.00% (regressed)
+emulator-5554  group/sort  2.00 ms   1.90 ms   -5.00%
+emulator-5554  new                   1.50 µs   new
+"
+        );
+    }
+}
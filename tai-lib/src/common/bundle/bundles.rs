@@ -1,12 +1,16 @@
 use std::{
-    fs::{copy, create_dir_all},
-    path::{Path, PathBuf},
+    fs::{copy, create_dir_all, symlink_metadata},
+    path::Path,
 };
 
-use anyhow::{Context, Error};
+use anyhow::{anyhow, bail, Context, Error};
 use tracing::debug;
+use walkdir::WalkDir;
 
-use crate::{common::compiler::BuiltUnit, TaiResult};
+use crate::{
+    common::{compiler::BuiltUnit, opts::Resource},
+    TaiResult,
+};
 
 use super::{BuiltBundle, BuiltBundles};
 
@@ -23,9 +27,15 @@ pub fn create_bundles(
     Ok(BuiltBundles { bundles })
 }
 
+/// Copies the resources that are needed by the test/bench target `target_name` into the data
+/// directory of the bundle.
+///
+/// A resource can be a file, a directory, which is copied recursively, or a glob pattern
+/// like `./data/*.json`. The files that match a pattern are copied into the directory `<id>`.
 pub fn copy_resources<P: AsRef<Path>>(
     dest_dir: P,
-    resources: &[(String, PathBuf)],
+    target_name: &str,
+    resources: &[Resource],
 ) -> TaiResult<()> {
     debug!("copy resources");
 
@@ -38,17 +48,181 @@ pub fn copy_resources<P: AsRef<Path>>(
     })?;
 
     debug!("create dir: {}", test_data_root.display());
-    let copied: TaiResult<Vec<()>> = resources
+    resources
         .iter()
-        .map(|(id, local_path)| {
+        .filter(|resource| resource.is_needed_by(target_name))
+        .try_for_each(|resource| {
+            let (id, local_path) = (&resource.id, &resource.local_path);
             let remote_path = test_data_root.join(id);
-            copy(local_path, &remote_path)
-                .with_context(|| format!("Failed to copy resource {}", local_path.display()))?;
-            debug!("copy {} to {}", local_path.display(), remote_path.display());
-            Ok(())
+            match is_glob_pattern(local_path) {
+                true => copy_glob_resource(id, local_path, &remote_path),
+                false => copy_resource(id, local_path, &remote_path),
+            }
         })
-        .collect();
-    copied?;
+}
 
+fn is_glob_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+fn copy_glob_resource(id: &str, pattern: &Path, remote_dir: &Path) -> TaiResult<()> {
+    let pattern = pattern.to_string_lossy();
+    let paths = glob::glob(&pattern)
+        .with_context(|| format!("Invalid pattern of resource {}: {}", id, pattern))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to read the files of resource {}", id))?;
+    if paths.is_empty() {
+        bail!(
+            "No file matches the pattern of resource {}: {}",
+            id,
+            pattern
+        )
+    }
+
+    create_dir_all(remote_dir)
+        .with_context(|| format!("Failed to create directory {}", remote_dir.display()))?;
+    paths.iter().try_for_each(|local_path| {
+        let file_name = local_path
+            .file_name()
+            .ok_or_else(|| anyhow!("Invalid path of resource {}: {}", id, local_path.display()))?;
+        copy_resource(id, local_path, &remote_dir.join(file_name))
+    })
+}
+
+fn copy_resource(id: &str, local_path: &Path, remote_path: &Path) -> TaiResult<()> {
+    let metadata = symlink_metadata(local_path)
+        .with_context(|| format!("Failed to read resource {}: {}", id, local_path.display()))?;
+    match metadata.is_dir() {
+        true => copy_dir(id, local_path, remote_path),
+        false => copy_file(id, local_path, remote_path),
+    }
+}
+
+fn copy_dir(id: &str, local_dir: &Path, remote_dir: &Path) -> TaiResult<()> {
+    WalkDir::new(local_dir)
+        .follow_links(false)
+        .into_iter()
+        .try_for_each(|entry| {
+            let entry = entry.with_context(|| {
+                format!("Failed to read resource {}: {}", id, local_dir.display())
+            })?;
+            let remote_path = remote_dir.join(entry.path().strip_prefix(local_dir)?);
+            match entry.file_type().is_dir() {
+                true => create_dir_all(&remote_path).with_context(|| {
+                    format!("Failed to create directory {}", remote_path.display())
+                }),
+                false => copy_file(id, entry.path(), &remote_path),
+            }
+        })
+}
+
+fn copy_file(id: &str, local_path: &Path, remote_path: &Path) -> TaiResult<()> {
+    let metadata = symlink_metadata(local_path)
+        .with_context(|| format!("Failed to read resource {}: {}", id, local_path.display()))?;
+    if metadata.file_type().is_symlink() {
+        bail!(
+            "Resource {} contains the symbolic link {}, which is not supported. \
+            Use the path of the file it points to instead.",
+            id,
+            local_path.display()
+        )
+    }
+
+    copy(local_path, remote_path).with_context(|| {
+        format!(
+            "Failed to copy file {} of resource {}",
+            local_path.display(),
+            id
+        )
+    })?;
+    debug!("copy {} to {}", local_path.display(), remote_path.display());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_copy_resources() {
+        let local = tempfile::tempdir().unwrap();
+        let data = local.path().join("data");
+        fs::create_dir_all(data.join("model/layers")).unwrap();
+        fs::write(data.join("test.txt"), "test").unwrap();
+        fs::write(data.join("a.json"), "{}").unwrap();
+        fs::write(data.join("b.json"), "[]").unwrap();
+        fs::write(data.join("model/config.toml"), "").unwrap();
+        fs::write(data.join("model/layers/0.bin"), "0").unwrap();
+        let bundle = tempfile::tempdir().unwrap();
+
+        copy_resources(
+            bundle.path(),
+            "integration",
+            &[
+                Resource::new("test_txt", data.join("test.txt")),
+                Resource::new("integration:model", data.join("model")),
+                Resource::new("bench:fixtures", data.join("*.json")),
+            ],
+        )
+        .unwrap();
+
+        let root = bundle.path().join(tai_util::DATA_DIR_NAME);
+        assert_eq!(fs::read_to_string(root.join("test_txt")).unwrap(), "test");
+        assert!(root.join("model/config.toml").is_file());
+        assert_eq!(
+            fs::read_to_string(root.join("model/layers/0.bin")).unwrap(),
+            "0"
+        );
+        assert!(!root.join("fixtures").exists());
+
+        let bundle = tempfile::tempdir().unwrap();
+        copy_resources(
+            bundle.path(),
+            "bench",
+            &[Resource::new("bench:fixtures", data.join("*.json"))],
+        )
+        .unwrap();
+
+        let root = bundle.path().join(tai_util::DATA_DIR_NAME);
+        assert_eq!(
+            fs::read_to_string(root.join("fixtures/a.json")).unwrap(),
+            "{}"
+        );
+        assert!(root.join("fixtures/b.json").is_file());
+    }
+
+    #[test]
+    fn test_copy_resources_errors() {
+        let local = tempfile::tempdir().unwrap();
+        let bundle = tempfile::tempdir().unwrap();
+
+        let err = copy_resources(
+            bundle.path(),
+            "integration",
+            &[Resource::new("fixtures", local.path().join("*.json"))],
+        )
+        .unwrap_err();
+        assert!(err.to_string().starts_with("No file matches"));
+
+        #[cfg(unix)]
+        {
+            fs::create_dir(local.path().join("data")).unwrap();
+            fs::write(local.path().join("test.txt"), "test").unwrap();
+            std::os::unix::fs::symlink(
+                local.path().join("test.txt"),
+                local.path().join("data/link.txt"),
+            )
+            .unwrap();
+
+            let err = copy_resources(
+                bundle.path(),
+                "integration",
+                &[Resource::new("data", local.path().join("data"))],
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains("symbolic link"));
+        }
+    }
+}
@@ -1,8 +1,28 @@
-use anyhow::bail;
-use cfg_expr::targets::{Arch, Os};
-use tracing::debug;
+use std::{
+    ffi::OsStr,
+    fs::{read_dir, remove_dir_all, remove_file},
+    path::{Path, PathBuf},
+};
 
-use crate::{android, common::opts::Options, ios, TaiResult};
+use anyhow::{anyhow, bail, Context as _};
+use cfg_expr::targets::{Arch, Os, TargetInfo};
+use tracing::{debug, warn};
+
+use crate::{
+    android,
+    common::{
+        baseline,
+        config::Config,
+        device_selection::NoDeviceAvailable,
+        devices::ReachableDevices,
+        opts::{CleanOptions, ListDevicesOptions, Options},
+        project::{CargoOptions, ProjectMetadata, CARGO_TAI_TARGET_DIR},
+        report::{Event, Reporter, RunReport, SkippedTarget},
+        task::context::Context,
+        tools::cargo_metadata,
+    },
+    ios, TaiResult,
+};
 
 #[derive(Debug, Clone)]
 pub enum Command {
@@ -12,16 +32,214 @@ pub enum Command {
     Tests,
 }
 
-pub fn run_command(requested: Options) -> TaiResult<()> {
-    debug!("run command with options:\n{:?}", requested);
-    match (requested.compiler.target.arch, requested.compiler.target.os) {
+/// Runs the command for every target, one after another.
+///
+/// `requested` contains the options for each target. The cargo metadata is read once and
+/// the reporter is shared between the targets, so that the summary covers all of them.
+/// Targets without a device that can run them are skipped.
+pub fn run_command(requested: Vec<Options>) -> TaiResult<()> {
+    let first = requested
+        .first()
+        .ok_or_else(|| anyhow!("no target requested"))?;
+    let meta = ProjectMetadata::from_cargo_args(&first.compiler.cargo_args)?;
+    let reporter = Reporter::new(&first.run, &meta.tai_target)?;
+    let baseline = match first.command {
+        Command::Bench | Command::Benches => first.run.baseline.clone(),
+        Command::Test | Command::Tests => None,
+    };
+
+    let mut report = RunReport::default();
+    for options in requested {
+        debug!("run command with options:\n{:?}", options);
+        let target = options.compiler.target.triple.to_string();
+        let no_fail_fast = options.run.no_fail_fast;
+
+        let mut context = Context::from(options);
+        context.insert(reporter.clone());
+        context.insert(meta.clone());
+        match run_target(context) {
+            Ok(target_report) => report.extend(target_report.results),
+            Err(err) => match err.downcast_ref::<NoDeviceAvailable>() {
+                Some(NoDeviceAvailable(reason)) => {
+                    let skipped = SkippedTarget {
+                        target,
+                        reason: reason.clone(),
+                    };
+                    reporter.emit(&Event::TargetSkipped(skipped.clone()));
+                    report.skip(skipped);
+                }
+                None => {
+                    reporter.finish(&report);
+                    return Err(err);
+                }
+            },
+        }
+        if report.failed() > 0 && !no_fail_fast {
+            break;
+        }
+    }
+
+    reporter.finish(&report);
+    if let (Some(baseline), 0) = (&baseline, report.failed()) {
+        baseline::process(baseline, &report, &reporter, &meta.tai_target)?;
+    }
+    report.into_result()
+}
+
+/// The pipeline that builds and runs the bundles of a target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Android,
+    IosDevice,
+    IosSimulator,
+}
+
+impl Platform {
+    /// The platform of `target`, `None` if cargo-tai does not support the target.
+    pub fn of(target: &TargetInfo) -> Option<Self> {
+        match (target.triple, target.arch, target.os) {
+            ("aarch64-apple-ios", _, _) => Some(Platform::IosDevice),
+            ("x86_64-apple-ios" | "aarch64-apple-ios-sim", _, _) => Some(Platform::IosSimulator),
+            (_, Arch::aarch64 | Arch::arm | Arch::x86 | Arch::x86_64, Some(Os::android)) => {
+                Some(Platform::Android)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn run_target(context: Context) -> TaiResult<RunReport> {
+    let target = &context.get::<Options>().compiler.target;
+    match Platform::of(target) {
         #[cfg(feature = "ios")]
-        (Arch::aarch64, Some(Os::ios)) => ios::platform::physical::run_command(requested),
+        Some(Platform::IosDevice) => ios::platform::physical::run_command(context),
         #[cfg(feature = "ios")]
-        (Arch::x86_64, Some(Os::ios)) => ios::platform::simulator::run_command(requested),
-        (Arch::aarch64 | Arch::arm | Arch::x86 | Arch::x86_64, Some(Os::android)) => {
-            android::platform::run_command(requested)
+        Some(Platform::IosSimulator) => ios::platform::simulator::run_command(context),
+        Some(Platform::Android) => android::platform::run_command(context),
+        _ => bail!("unsupported target: {:?}", target),
+    }
+}
+
+/// Prints all devices, emulators and simulators that cargo-tai can reach.
+///
+/// A platform whose devices cannot be listed (e.g. because `adb` is not installed)
+/// is skipped with a warning.
+pub fn devices_command(requested: ListDevicesOptions) -> TaiResult<()> {
+    debug!("list devices with options:\n{:?}", requested);
+    let mut devices = Vec::new();
+    match android::platform::list_devices(&requested) {
+        Ok(android_devices) => devices.extend(android_devices),
+        Err(err) => warn!("failed to list Android devices: {:#}", err),
+    }
+    #[cfg(feature = "ios")]
+    if cfg!(target_os = "macos") {
+        devices.extend(ios::platform::list_devices());
+    }
+
+    match requested.json {
+        true => println!("{}", serde_json::to_string_pretty(&devices)?),
+        false => print!("{}", ReachableDevices(devices)),
+    }
+    Ok(())
+}
+
+/// Prints the effective configuration in the format of `Tai.toml`.
+pub fn config_command(config: Config) -> TaiResult<()> {
+    print!("{}", toml::to_string(&config)?);
+    Ok(())
+}
+
+/// Removes the bundles and caches of cargo-tai from the target directory and, with `device`,
+/// the resource caches from the Android devices. The saved baselines are kept.
+pub fn clean_command(requested: CleanOptions) -> TaiResult<()> {
+    debug!("clean with options:\n{:?}", requested);
+    let manifest_path = CargoOptions::from_cargo_args(&requested.cargo_args)?.manifest_path;
+    let tai_target = cargo_metadata(&manifest_path)?
+        .target_directory
+        .join(CARGO_TAI_TARGET_DIR);
+    for removed in clean_tai_target(tai_target.as_std_path())? {
+        println!("removed {}", removed.display());
+    }
+
+    if requested.device {
+        let project_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+        for device in android::platform::clean_devices(&requested, project_dir)? {
+            println!("removed the resource cache on {}", device);
+        }
+    }
+    Ok(())
+}
+
+/// Removes everything in the cargo-tai target directory except the baselines and returns
+/// the removed paths.
+fn clean_tai_target(tai_target: &Path) -> TaiResult<Vec<PathBuf>> {
+    if !tai_target.exists() {
+        return Ok(Vec::new());
+    }
+    let mut removed = Vec::new();
+    let entries =
+        read_dir(tai_target).with_context(|| format!("Failed to read {}", tai_target.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.file_name() == Some(OsStr::new(baseline::BASELINES_DIR)) {
+            continue;
+        }
+        match path.is_dir() {
+            true => remove_dir_all(&path),
+            false => remove_file(&path),
         }
-        _ => bail!("unsupported target: {:?}", requested.compiler.target),
+        .with_context(|| format!("Failed to remove {}", path.display()))?;
+        removed.push(path);
+    }
+    removed.sort();
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use cfg_expr::targets::get_builtin_target_by_triple;
+
+    use super::*;
+
+    fn platform(triple: &str) -> Option<Platform> {
+        Platform::of(get_builtin_target_by_triple(triple).unwrap())
+    }
+
+    #[test]
+    fn test_platform() {
+        assert_eq!(platform("aarch64-apple-ios"), Some(Platform::IosDevice));
+        assert_eq!(platform("x86_64-apple-ios"), Some(Platform::IosSimulator));
+        assert_eq!(
+            platform("aarch64-apple-ios-sim"),
+            Some(Platform::IosSimulator)
+        );
+        assert_eq!(platform("aarch64-linux-android"), Some(Platform::Android));
+        assert_eq!(platform("armv7-linux-androideabi"), Some(Platform::Android));
+        assert_eq!(platform("i686-linux-android"), Some(Platform::Android));
+        assert_eq!(platform("x86_64-linux-android"), Some(Platform::Android));
+        assert_eq!(platform("armv7-apple-ios"), None);
+        assert_eq!(platform("x86_64-apple-ios-macabi"), None);
+        assert_eq!(platform("x86_64-unknown-linux-gnu"), None);
+    }
+
+    #[test]
+    fn test_clean_keeps_baselines() {
+        let dir = tempfile::tempdir().unwrap();
+        let tai_target = dir.path().join("cargo-tai");
+        let baseline = tai_target.join(baseline::BASELINES_DIR).join("device/main");
+        std::fs::create_dir_all(&baseline).unwrap();
+        std::fs::create_dir_all(tai_target.join("artifacts")).unwrap();
+        std::fs::write(tai_target.join("report.json"), "").unwrap();
+
+        assert_eq!(
+            clean_tai_target(&tai_target).unwrap(),
+            vec![tai_target.join("artifacts"), tai_target.join("report.json")]
+        );
+        assert!(baseline.exists());
+        assert_eq!(read_dir(&tai_target).unwrap().count(), 1);
+
+        assert!(clean_tai_target(&dir.path().join("missing"))
+            .unwrap()
+            .is_empty());
     }
 }
@@ -8,6 +8,8 @@ use util::{compile, is_bench, is_test};
 #[derive(Debug)]
 pub struct BuiltUnit {
     pub name: String,
+    /// The name of the cargo target, e.g. `integration` for `tests/integration.rs`
+    pub target_name: String,
     pub artifact: PathBuf,
     pub target: TargetInfo<'static>,
 }
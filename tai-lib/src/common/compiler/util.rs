@@ -47,12 +47,14 @@ pub fn compile<F: Fn(Artifact) -> Option<Utf8PathBuf>>(
         .into_iter()
         .try_fold(vec![], |mut acc, msg| match msg? {
             Message::CompilerArtifact(art) => {
+                let target_name = art.target.name.clone();
                 if let Some(path) = f(art) {
                     let unit = BuiltUnit {
                         name: path
                             .file_name()
                             .ok_or_else(|| anyhow!("build artifact should have a name"))?
                             .to_string(),
+                        target_name,
                         artifact: path.into(),
                         target: requested.target.clone(),
                     };
@@ -0,0 +1,328 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{
+        opts::{AndroidOptions, IosOptions, Resource},
+        project::CargoOptions,
+    },
+    TaiResult,
+};
+
+/// The name of the optional configuration file next to the manifest.
+pub const CONFIG_FILE: &str = "Tai.toml";
+
+/// The cargo-tai configuration of a project.
+///
+/// It is read from the `[package.metadata.tai]` table of the manifest and from `Tai.toml`.
+/// Example:
+///
+/// ```toml
+/// android-api-lvl = 21
+/// android-ndk = "/opt/android-ndk"
+///
+/// [resources]
+/// test_txt = "data/test.txt"
+/// "integration:model" = "data/model"
+///
+/// [target.aarch64-apple-ios]
+/// ios-mobile-provision = "cargo-tai.mobileprovision"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    /// The values for all targets
+    #[serde(flatten)]
+    pub base: TargetConfig,
+    /// The values for a single target, which take precedence over `base`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub target: BTreeMap<String, TargetConfig>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TargetConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub android_api_lvl: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub android_sdk: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub android_ndk: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub android_ndk_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ios_mobile_provision: Option<PathBuf>,
+    /// Arguments that are passed to the binaries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+    /// `id = "local path"` or `"binary:id" = "local path"` for a resource that is only
+    /// included in the bundle of one test/bench target
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub resources: BTreeMap<String, PathBuf>,
+    /// Environment variables that are set for the binaries
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub envs: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// Reads the configuration of the manifest that is selected by the cargo arguments
+    /// (`--manifest-path`). The values of `Tai.toml` take precedence over the ones of
+    /// `[package.metadata.tai]`. Relative paths are resolved against the directory of the manifest.
+    pub fn load(cargo_args: &[String]) -> TaiResult<Self> {
+        let manifest_path = CargoOptions::from_cargo_args(cargo_args)?.manifest_path;
+        let project_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+
+        let manifest = read_toml(&manifest_path)?;
+        let metadata = manifest
+            .get("package")
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get("tai"))
+            .cloned()
+            .map(|tai| {
+                tai.try_into::<Config>().with_context(|| {
+                    format!(
+                        "Invalid [package.metadata.tai] in {}",
+                        manifest_path.display()
+                    )
+                })
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let config_path = project_dir.join(CONFIG_FILE);
+        let config_file = match config_path.exists() {
+            true => read_toml(&config_path)?
+                .try_into::<Config>()
+                .with_context(|| format!("Invalid {}", config_path.display()))?,
+            false => Config::default(),
+        };
+
+        let mut config = config_file.or(metadata);
+        config.resolve_paths(project_dir);
+        Ok(config)
+    }
+
+    /// The configuration of `target`: the values of the target section and the ones for all targets.
+    pub fn target(&self, target: &str) -> TargetConfig {
+        self.target
+            .get(target)
+            .cloned()
+            .unwrap_or_default()
+            .or(self.base.clone())
+    }
+
+    /// Fills the values that are not set in `self` with the ones of `fallback`.
+    pub fn or(mut self, fallback: Config) -> Config {
+        for (target, fallback) in fallback.target {
+            let config = self.target.remove(&target).unwrap_or_default().or(fallback);
+            self.target.insert(target, config);
+        }
+        Config {
+            base: self.base.or(fallback.base),
+            target: self.target,
+        }
+    }
+
+    fn resolve_paths(&mut self, dir: &Path) {
+        self.base.resolve_paths(dir);
+        self.target
+            .values_mut()
+            .for_each(|config| config.resolve_paths(dir));
+    }
+}
+
+impl TargetConfig {
+    /// Fills the values that are not set in `self` with the ones of `fallback`.
+    /// Resources and environment variables are merged by their id/name.
+    pub fn or(self, fallback: TargetConfig) -> TargetConfig {
+        let mut resources = fallback.resources;
+        resources.extend(self.resources);
+        let mut envs = fallback.envs;
+        envs.extend(self.envs);
+
+        TargetConfig {
+            android_api_lvl: self.android_api_lvl.or(fallback.android_api_lvl),
+            android_sdk: self.android_sdk.or(fallback.android_sdk),
+            android_ndk: self.android_ndk.or(fallback.android_ndk),
+            android_ndk_version: self.android_ndk_version.or(fallback.android_ndk_version),
+            ios_mobile_provision: self.ios_mobile_provision.or(fallback.ios_mobile_provision),
+            args: self.args.or(fallback.args),
+            resources,
+            envs,
+        }
+    }
+
+    /// `None` if the api level is missing. The sdk and the ndk are discovered if not set.
+    pub fn android_options(&self) -> Option<AndroidOptions> {
+        self.android_api_lvl.map(|api_lvl| AndroidOptions {
+            api_lvl,
+            sdk: self.android_sdk.clone(),
+            ndk: self.android_ndk.clone(),
+            ndk_version: self.android_ndk_version.clone(),
+        })
+    }
+
+    pub fn ios_options(&self) -> Option<IosOptions> {
+        self.ios_mobile_provision
+            .clone()
+            .map(|mobile_provision| IosOptions { mobile_provision })
+    }
+
+    pub fn resources(&self) -> Option<Vec<Resource>> {
+        match self.resources.is_empty() {
+            true => None,
+            false => Some(
+                self.resources
+                    .iter()
+                    .map(|(key, path)| Resource::new(key, path.clone()))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn envs(&self) -> Option<Vec<(String, String)>> {
+        match self.envs.is_empty() {
+            true => None,
+            false => Some(
+                self.envs
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn resolve_paths(&mut self, dir: &Path) {
+        let paths = [
+            self.android_sdk.as_mut(),
+            self.android_ndk.as_mut(),
+            self.ios_mobile_provision.as_mut(),
+        ];
+        paths
+            .into_iter()
+            .flatten()
+            .chain(self.resources.values_mut())
+            .filter(|path| path.is_relative())
+            .for_each(|path| *path = dir.join(&*path));
+    }
+}
+
+fn read_toml(path: &Path) -> TaiResult<toml::Value> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_config() {
+        let config: Config = toml::from_str(
+            r#"
+android-api-lvl = 21
+android-ndk = "/opt/ndk"
+envs = { RUST_LOG = "info", RUST_BACKTRACE = "1" }
+
+[target.aarch64-linux-android]
+android-api-lvl = 24
+envs = { RUST_LOG = "debug" }
+"#,
+        )
+        .unwrap();
+
+        let target = config.target("aarch64-linux-android");
+        assert_eq!(target.android_api_lvl, Some(24));
+        assert_eq!(target.android_ndk, Some(PathBuf::from("/opt/ndk")));
+        assert_eq!(
+            target.envs(),
+            Some(vec![
+                ("RUST_BACKTRACE".to_string(), "1".to_string()),
+                ("RUST_LOG".to_string(), "debug".to_string())
+            ])
+        );
+        assert_eq!(
+            config.target("x86_64-linux-android").android_api_lvl,
+            Some(21)
+        );
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            r#"
+[package]
+name = "test-project"
+version = "0.1.0"
+
+[package.metadata.tai]
+android-api-lvl = 21
+resources = { test_txt = "data/test.txt", "integration:model" = "data/model" }
+
+[package.metadata.tai.target.aarch64-apple-ios]
+ios-mobile-provision = "manifest.mobileprovision"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            r#"
+android-ndk = "/opt/ndk"
+
+[target.aarch64-apple-ios]
+ios-mobile-provision = "tai.mobileprovision"
+"#,
+        )
+        .unwrap();
+        let manifest_path = dir.path().join("Cargo.toml");
+
+        let config = Config::load(&[
+            "--manifest-path".to_string(),
+            manifest_path.display().to_string(),
+        ])
+        .unwrap();
+
+        let target = config.target("aarch64-apple-ios");
+        assert_eq!(target.android_api_lvl, Some(21));
+        assert_eq!(target.android_ndk, Some(PathBuf::from("/opt/ndk")));
+        assert_eq!(
+            target.ios_mobile_provision,
+            Some(dir.path().join("tai.mobileprovision"))
+        );
+        assert_eq!(
+            target.resources(),
+            Some(vec![
+                Resource::new("integration:model", dir.path().join("data/model")),
+                Resource::new("test_txt", dir.path().join("data/test.txt"))
+            ])
+        );
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut config = Config::default();
+        config.base.android_api_lvl = Some(21);
+        config
+            .base
+            .resources
+            .insert("test_txt".to_string(), PathBuf::from("data/test.txt"));
+        config.target.insert(
+            "aarch64-apple-ios".to_string(),
+            TargetConfig {
+                args: Some(vec!["--nocapture".to_string()]),
+                ..Default::default()
+            },
+        );
+
+        let toml = toml::to_string(&config).unwrap();
+        assert_eq!(toml::from_str::<Config>(&toml).unwrap(), config);
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use anyhow::{anyhow, bail};
+use regex::{Regex, RegexBuilder};
+
+use crate::TaiResult;
+
+use super::opts::DeviceOptions;
+
+/// The properties of a device that the `--device` patterns are matched against.
+#[derive(Debug, Default)]
+pub struct DeviceProperties<'a> {
+    /// Serial (Android) or UDID (iOS)
+    pub id: &'a str,
+    pub name: Option<&'a str>,
+    pub model: Option<&'a str>,
+    pub api_level: Option<u32>,
+}
+
+impl Display for DeviceProperties<'_> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let details = [
+            self.name.map(ToString::to_string),
+            self.model.map(ToString::to_string),
+            self.api_level.map(|api_level| format!("API {}", api_level)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        match details.is_empty() {
+            true => write!(fmt, "{}", self.id),
+            false => write!(fmt, "{} ({})", self.id, details.join(", ")),
+        }
+    }
+}
+
+/// No device is available that can run the bundles of a target.
+///
+/// When several targets are run, targets that fail with this error are skipped.
+#[derive(Debug)]
+pub struct NoDeviceAvailable(pub String);
+
+impl Display for NoDeviceAvailable {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+impl Error for NoDeviceAvailable {}
+
+enum DevicePattern {
+    ApiLevel(u32),
+    Glob(Regex),
+}
+
+impl DevicePattern {
+    /// `api=<level>` matches the API level, every other pattern is matched
+    /// case-insensitively against the id, name and model. `*` matches any characters.
+    fn parse(pattern: &str) -> TaiResult<Self> {
+        if let Some(api_level) = pattern.strip_prefix("api=") {
+            return api_level
+                .parse()
+                .map(Self::ApiLevel)
+                .map_err(|_| anyhow!("invalid API level in device pattern `{}`", pattern));
+        }
+
+        let regex = pattern
+            .split('*')
+            .map(regex::escape)
+            .collect::<Vec<_>>()
+            .join(".*");
+        let regex = RegexBuilder::new(&format!("^{}$", regex))
+            .case_insensitive(true)
+            .build()?;
+        Ok(Self::Glob(regex))
+    }
+
+    fn matches(&self, device: &DeviceProperties) -> bool {
+        match self {
+            Self::ApiLevel(api_level) => device.api_level == Some(*api_level),
+            Self::Glob(regex) => [Some(device.id), device.name, device.model]
+                .into_iter()
+                .flatten()
+                .any(|value| regex.is_match(value)),
+        }
+    }
+}
+
+/// Keeps the devices that match at least one of the `--device` patterns (all devices if
+/// there are no patterns) and only the first of them if `--first-device` is set.
+///
+/// Fails with a list of the available devices if no device matches, and with
+/// [`NoDeviceAvailable`] if there are no devices at all.
+pub fn select_devices<T, F>(
+    devices: Vec<T>,
+    opts: &DeviceOptions,
+    properties: F,
+) -> TaiResult<Vec<T>>
+where
+    F: for<'a> Fn(&'a T) -> DeviceProperties<'a>,
+{
+    let patterns = opts
+        .patterns
+        .iter()
+        .map(|pattern| DevicePattern::parse(pattern))
+        .collect::<TaiResult<Vec<_>>>()?;
+
+    let (selected, rejected): (Vec<T>, Vec<T>) = devices.into_iter().partition(|device| {
+        patterns.is_empty()
+            || patterns
+                .iter()
+                .any(|pattern| pattern.matches(&properties(device)))
+    });
+
+    if selected.is_empty() && rejected.is_empty() {
+        return Err(NoDeviceAvailable("no device available".to_string()).into());
+    }
+    // not a `NoDeviceAvailable`: a pattern that matches nothing must not skip the target
+    if selected.is_empty() {
+        let available = rejected
+            .iter()
+            .map(|device| format!("\n    {}", properties(device)))
+            .collect::<String>();
+        bail!(
+            "no device matches {}. Available devices:{}",
+            opts.patterns
+                .iter()
+                .map(|pattern| format!("`--device {}`", pattern))
+                .collect::<Vec<_>>()
+                .join(", "),
+            available
+        );
+    }
+
+    match opts.first {
+        true => Ok(selected.into_iter().take(1).collect()),
+        false => Ok(selected),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Device {
+        id: &'static str,
+        model: &'static str,
+        api_level: u32,
+    }
+
+    fn devices() -> Vec<Device> {
+        vec![
+            Device {
+                id: "emulator-5554",
+                model: "sdk_gphone64_x86_64",
+                api_level: 33,
+            },
+            Device {
+                id: "R58M123",
+                model: "Pixel 6",
+                api_level: 31,
+            },
+            Device {
+                id: "R58M456",
+                model: "Pixel 6",
+                api_level: 33,
+            },
+        ]
+    }
+
+    fn select(patterns: &[&str], first: bool) -> TaiResult<Vec<&'static str>> {
+        let opts = DeviceOptions {
+            patterns: patterns.iter().map(ToString::to_string).collect(),
+            first,
+            ..Default::default()
+        };
+        select_devices(devices(), &opts, |device| DeviceProperties {
+            id: device.id,
+            model: Some(device.model),
+            api_level: Some(device.api_level),
+            ..Default::default()
+        })
+        .map(|devices| devices.into_iter().map(|device| device.id).collect())
+    }
+
+    #[test]
+    fn test_select_devices() {
+        assert_eq!(
+            select(&[], false).unwrap(),
+            vec!["emulator-5554", "R58M123", "R58M456"]
+        );
+        assert_eq!(select(&["r58m123"], false).unwrap(), vec!["R58M123"]);
+        assert_eq!(
+            select(&["pixel 6"], false).unwrap(),
+            vec!["R58M123", "R58M456"]
+        );
+        assert_eq!(
+            select(&["emulator-*", "api=31"], false).unwrap(),
+            vec!["emulator-5554", "R58M123"]
+        );
+        assert_eq!(select(&["Pixel*"], true).unwrap(), vec!["R58M123"]);
+        assert_eq!(select(&[], true).unwrap(), vec!["emulator-5554"]);
+    }
+
+    #[test]
+    fn test_select_devices_no_match() {
+        let err = select(&["Pixel 7", "api=21"], false).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "no device matches `--device Pixel 7`, `--device api=21`. Available devices:
+    emulator-5554 (sdk_gphone64_x86_64, API 33)
+    R58M123 (Pixel 6, API 31)
+    R58M456 (Pixel 6, API 33)"
+        );
+        assert!(err.downcast_ref::<NoDeviceAvailable>().is_none());
+        assert!(select(&["api=latest"], false).is_err());
+
+        let err = select_devices(Vec::<Device>::new(), &DeviceOptions::default(), |device| {
+            DeviceProperties {
+                id: device.id,
+                ..Default::default()
+            }
+        })
+        .unwrap_err();
+        assert!(err.downcast_ref::<NoDeviceAvailable>().is_some());
+    }
+}
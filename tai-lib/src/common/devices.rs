@@ -0,0 +1,85 @@
+use std::fmt::{self, Display, Formatter};
+
+use serde::Serialize;
+
+use super::table::write_table;
+
+/// A device, emulator or simulator that cargo-tai can reach.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReachableDevice {
+    /// `android`, `ios-simulator` or `ios`
+    pub platform: &'static str,
+    /// Serial (Android) or UDID (iOS)
+    pub id: String,
+    pub name: Option<String>,
+    pub os_version: Option<String>,
+    /// The ABI or CPU architecture as reported by the device
+    pub arch: String,
+    /// The `--target` triples whose binaries can run on the device.
+    /// Empty if cargo-tai does not support the device.
+    pub targets: Vec<&'static str>,
+}
+
+/// The devices of all platforms, printed as a table.
+pub struct ReachableDevices(pub Vec<ReachableDevice>);
+
+impl Display for ReachableDevices {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        const HEADER: [&str; 6] = ["platform", "id", "name", "os", "arch", "targets"];
+
+        let rows = self
+            .0
+            .iter()
+            .map(|device| {
+                vec![
+                    device.platform.to_string(),
+                    device.id.clone(),
+                    device.name.clone().unwrap_or_default(),
+                    device.os_version.clone().unwrap_or_default(),
+                    device.arch.clone(),
+                    match device.targets.is_empty() {
+                        true => "unsupported".to_string(),
+                        false => device.targets.join(", "),
+                    },
+                ]
+            })
+            .collect::<Vec<_>>();
+        write_table(fmt, &HEADER, &rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_devices_table() {
+        let devices = ReachableDevices(vec![
+            ReachableDevice {
+                platform: "android",
+                id: "emulator-5554".to_string(),
+                name: Some("sdk_gphone64_x86_64".to_string()),
+                os_version: Some("13 (API 33)".to_string()),
+                arch: "x86_64".to_string(),
+                targets: vec!["x86_64-linux-android"],
+            },
+            ReachableDevice {
+                platform: "android",
+                id: "mips-1".to_string(),
+                name: None,
+                os_version: None,
+                arch: "mips".to_string(),
+                targets: vec![],
+            },
+        ]);
+
+        assert_eq!(
+            devices.to_string(),
+            "\
+platform  id             name                 os           arch    targets
+android   emulator-5554  sdk_gphone64_x86_64  13 (API 33)  x86_64  x86_64-linux-android
+android   mips-1                                           mips    unsupported
+"
+        );
+    }
+}
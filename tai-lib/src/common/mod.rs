@@ -1,7 +1,14 @@
+pub mod baseline;
 pub mod bundle;
 pub mod command;
 pub mod compiler;
+pub mod config;
+pub mod device_selection;
+pub mod devices;
 pub mod opts;
 pub mod project;
+pub mod report;
+pub mod table;
 pub mod task;
+pub mod timeout;
 pub mod tools;
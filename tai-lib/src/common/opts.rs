@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use cfg_expr::targets::TargetInfo;
 
@@ -8,8 +8,10 @@ use super::command::Command;
 pub struct Options {
     pub command: Command,
     pub compiler: CompilerOptions,
-    pub resources: Option<Vec<(String, PathBuf)>>,
+    pub resources: Option<Vec<Resource>>,
     pub binary: Option<BinaryOptions>,
+    pub run: RunOptions,
+    pub devices: DeviceOptions,
     pub android: Option<AndroidOptions>,
     pub ios: Option<IosOptions>,
     pub cli: CliOptions,
@@ -24,18 +26,199 @@ pub struct CompilerOptions {
     pub cargo_args: Vec<String>,
 }
 
+/// A resource that is copied into the data directory of the bundles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource {
+    /// The test/bench target that needs the resource, `None` for all targets
+    pub binary: Option<String>,
+    pub id: String,
+    pub local_path: PathBuf,
+}
+
+impl Resource {
+    /// Creates a resource from a key of the form `[<binary>:]<id>`,
+    /// e.g. `integration:model` or `model`.
+    pub fn new(key: &str, local_path: PathBuf) -> Self {
+        let (binary, id) = match key.split_once(':') {
+            Some((binary, id)) => (Some(binary.to_string()), id.to_string()),
+            None => (None, key.to_string()),
+        };
+        Self {
+            binary,
+            id,
+            local_path,
+        }
+    }
+
+    /// Whether the resource is needed by the test/bench target `target_name`.
+    /// Like cargo, `-` and `_` are not distinguished.
+    pub fn is_needed_by(&self, target_name: &str) -> bool {
+        match &self.binary {
+            Some(binary) => binary.replace('-', "_") == target_name.replace('-', "_"),
+            None => true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct BinaryOptions {
     pub args: Option<Vec<String>>,
     pub envs: Option<Vec<(String, String)>>,
+    /// Kill a binary that is still running after this duration
+    pub timeout: Option<Duration>,
+    /// Only run the tests/benchmarks whose name contains this filter
+    pub filter: Option<String>,
+    /// Only run the tests/benchmarks whose name is exactly the filter
+    pub exact: bool,
+    /// Only run the ignored tests
+    pub ignored: bool,
+    /// Skip the tests/benchmarks whose name contains one of these filters
+    pub skip: Option<Vec<String>>,
+}
+
+impl BinaryOptions {
+    /// The arguments of the binary: `args` followed by the libtest arguments of the filters.
+    pub fn binary_args(&self) -> Vec<String> {
+        let mut args = self.args.clone().unwrap_or_default();
+        if self.exact {
+            args.push("--exact".to_string());
+        }
+        if self.ignored {
+            args.push("--ignored".to_string());
+        }
+        for skip in self.skip.iter().flatten() {
+            args.push("--skip".to_string());
+            args.push(skip.clone());
+        }
+        if let Some(filter) = &self.filter {
+            // a filter that starts with `-` must not be parsed as an option
+            if filter.starts_with('-') {
+                args.push("--".to_string());
+            }
+            args.push(filter.clone());
+        }
+        args
+    }
+}
+
+/// The directory that `--artifacts` copies back if no directory is given, which is the one
+/// of `tai_util::dirs::artifacts_dir()` and contains the reports of `tai_util::criterion`.
+pub const DEFAULT_ARTIFACTS_DIR: &str = tai_util::ARTIFACTS_DIR_NAME;
+
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    /// Number of devices on which the bundles are run in parallel
+    pub jobs: usize,
+    /// Run all bundles even if some of them fail
+    pub no_fail_fast: bool,
+    /// Time limit for all bundles on a device
+    pub device_timeout: Option<Duration>,
+    pub message_format: MessageFormat,
+    /// Write the events of the run as JSON lines to this file
+    pub report: Option<PathBuf>,
+    /// Write a JUnit XML file per device and bundle into this directory
+    pub junit: Option<PathBuf>,
+    /// Copy this directory of each bundle back from the device after the run
+    pub artifacts: Option<PathBuf>,
+    /// Save and/or compare the criterion estimates of the benchmarks
+    pub baseline: Option<BaselineOptions>,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            jobs: 1,
+            no_fail_fast: false,
+            device_timeout: None,
+            message_format: MessageFormat::Human,
+            report: None,
+            junit: None,
+            artifacts: None,
+            baseline: None,
+        }
+    }
+}
+
+/// Criterion baselines of a `bench`/`benches` run, which need the artifacts (`--artifacts`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaselineOptions {
+    /// Save the estimates as baseline with this name
+    pub save: Option<String>,
+    /// Compare the estimates with the baseline with this name
+    pub compare: Option<String>,
+    /// Fail if a benchmark is slower than the baseline by more than this percentage
+    pub threshold: f64,
+}
+
+/// The default of `BaselineOptions::threshold`.
+pub const DEFAULT_REGRESSION_THRESHOLD: f64 = 5.0;
+
+#[derive(Debug, Clone, Default)]
+pub struct DeviceOptions {
+    /// Only use the devices that match one of the patterns
+    pub patterns: Vec<String>,
+    /// Only use the first (matching) device
+    pub first: bool,
+    /// Boot, and create if necessary, an iOS simulator for the run
+    pub simulator: Option<SimulatorOptions>,
+    /// Start an Android emulator for the run
+    pub emulator: Option<EmulatorOptions>,
+}
+
+/// An Android emulator that is started before the bundles are run and killed afterwards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmulatorOptions {
+    /// The name of the AVD, e.g. `Pixel_6_API_33`
+    pub avd: String,
+    /// Run the emulator without a window
+    pub headless: bool,
+}
+
+/// An iOS simulator that is booted, and created if necessary, before the bundles are run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimulatorOptions {
+    /// `<device type>[/<runtime>]`, e.g. `iPhone 14/iOS 16.2`. Without it, any available
+    /// simulator is booted if none is booted yet.
+    pub spec: Option<String>,
+    /// Create a new simulator and delete it after the run
+    pub ephemeral: bool,
+    /// Shut down the simulator after the run if it was booted for the run
+    pub shutdown: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
+/// Options of `cargo-tai devices`
+#[derive(Debug, Clone, Default)]
+pub struct ListDevicesOptions {
+    pub json: bool,
+    pub android_sdk: Option<PathBuf>,
+    pub android_ndk: Option<PathBuf>,
+}
+
+/// Options of `cargo-tai clean`
+#[derive(Debug, Clone, Default)]
+pub struct CleanOptions {
+    /// Also remove the resource cache from all Android devices
+    pub device: bool,
+    pub cargo_args: Vec<String>,
+    pub android_sdk: Option<PathBuf>,
+    pub android_ndk: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
 pub struct AndroidOptions {
     pub api_lvl: u8,
+    /// Discovered if not set
     pub sdk: Option<PathBuf>,
-    pub ndk: PathBuf,
-    pub cargo_ndk_args: Option<Vec<String>>,
+    /// Discovered if not set
+    pub ndk: Option<PathBuf>,
+    /// The version of the ndk to look for in the sdk, e.g. `25`
+    pub ndk_version: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -47,3 +230,54 @@ pub struct IosOptions {
 pub struct CliOptions {
     pub verbose: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_args() {
+        let binary = BinaryOptions {
+            args: Some(vec!["--nocapture".to_string()]),
+            filter: Some("tests::it works".to_string()),
+            exact: true,
+            ignored: true,
+            skip: Some(vec!["slow".to_string(), "very slow".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            binary.binary_args(),
+            vec![
+                "--nocapture",
+                "--exact",
+                "--ignored",
+                "--skip",
+                "slow",
+                "--skip",
+                "very slow",
+                "tests::it works"
+            ]
+        );
+
+        let binary = BinaryOptions {
+            filter: Some("-x".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(binary.binary_args(), vec!["--", "-x"]);
+        assert!(BinaryOptions::default().binary_args().is_empty());
+    }
+
+    #[test]
+    fn test_resource() {
+        let resource = Resource::new("model", PathBuf::from("m.bin"));
+        assert_eq!(resource.binary, None);
+        assert_eq!(resource.id, "model");
+        assert!(resource.is_needed_by("integration"));
+
+        let resource = Resource::new("my-integration:model", PathBuf::from("m.bin"));
+        assert_eq!(resource.binary.as_deref(), Some("my-integration"));
+        assert_eq!(resource.id, "model");
+        assert!(resource.is_needed_by("my_integration"));
+        assert!(!resource.is_needed_by("bench"));
+    }
+}
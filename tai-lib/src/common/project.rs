@@ -7,7 +7,10 @@ use crate::TaiResult;
 
 pub const CARGO_TAI_TARGET_DIR: &str = "cargo-tai";
 pub const IOS_CACHE_DIR: &str = "cache-ios";
+/// The directory in [`CARGO_TAI_TARGET_DIR`] into which the artifacts are copied.
+pub const ARTIFACTS_DIR: &str = "artifacts";
 
+#[derive(Clone)]
 pub struct ProjectMetadata {
     pub meta: Metadata,
     pub cargo_opts: CargoOptions,
@@ -15,6 +18,7 @@ pub struct ProjectMetadata {
     pub ios_cache: PathBuf,
 }
 
+#[derive(Clone)]
 pub struct CargoOptions {
     pub manifest_path: PathBuf,
     pub profile: Profile,
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::{bundle::BuiltBundle, compiler::BuiltUnit};
+
+use super::{BundleResult, SkippedTarget};
+
+/// A machine-readable event that is emitted while `cargo-tai` is running.
+///
+/// Events are serialized as one JSON object per line. The `reason` field
+/// identifies the kind of event, similar to the messages of `cargo build --message-format json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum Event {
+    /// A test or benchmark binary was built.
+    BuildArtifact {
+        name: String,
+        path: PathBuf,
+        target: String,
+    },
+    /// A bundle was created for a test or benchmark binary.
+    BundleCreated { name: String, root: PathBuf },
+    /// The devices on which the bundles will be run.
+    DevicesSelected { devices: Vec<DeviceInfo> },
+    /// A bundle was run on a device.
+    BundleFinished(BundleResult),
+    /// A target was not run because no device could run its bundles.
+    TargetSkipped(SkippedTarget),
+    /// All bundles were run.
+    RunFinished { passed: usize, failed: usize },
+}
+
+impl Event {
+    pub fn build_artifact(unit: &BuiltUnit) -> Self {
+        Self::BuildArtifact {
+            name: unit.name.clone(),
+            path: unit.artifact.clone(),
+            target: unit.target.triple.to_string(),
+        }
+    }
+
+    pub fn bundle_created(bundle: &BuiltBundle) -> Self {
+        Self::BundleCreated {
+            name: bundle.build_unit.name.clone(),
+            root: bundle.root.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    pub id: String,
+    pub name: Option<String>,
+    pub arch: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::common::report::RunStatus;
+
+    use super::*;
+
+    #[test]
+    fn test_serialize_bundle_finished() {
+        let event = Event::BundleFinished(BundleResult {
+            device: "emulator-5554".to_string(),
+            bundle: "integration-1234".to_string(),
+            status: RunStatus::Failed {
+                exit_code: Some(101),
+            },
+            duration: Duration::from_millis(1500),
+        });
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            r#"{"reason":"bundle-finished","device":"emulator-5554","bundle":"integration-1234","status":"failed","exit_code":101,"duration":1.5}"#
+        );
+        assert_eq!(serde_json::from_str::<Event>(&json).unwrap(), event);
+    }
+
+    #[test]
+    fn test_serialize_devices_selected() {
+        let event = Event::DevicesSelected {
+            devices: vec![DeviceInfo {
+                id: "R58M123".to_string(),
+                name: None,
+                arch: Some("aarch64".to_string()),
+            }],
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            r#"{"reason":"devices-selected","devices":[{"id":"R58M123","name":null,"arch":"aarch64"}]}"#
+        );
+        assert_eq!(serde_json::from_str::<Event>(&json).unwrap(), event);
+    }
+}
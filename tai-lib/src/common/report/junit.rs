@@ -0,0 +1,315 @@
+use std::fmt::{self, Display, Formatter, Write};
+
+/// The results of the tests of one test binary, as printed by libtest.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TestSuite {
+    pub cases: Vec<TestCase>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TestCase {
+    pub name: String,
+    pub outcome: TestOutcome,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TestOutcome {
+    Passed,
+    /// The test failed. `output` contains the captured output of the test
+    /// (e.g. the panic message) if libtest printed it.
+    Failed {
+        output: Option<String>,
+    },
+    Ignored,
+}
+
+impl TestSuite {
+    pub fn failures(&self) -> usize {
+        self.count(|outcome| matches!(outcome, TestOutcome::Failed { .. }))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.count(|outcome| matches!(outcome, TestOutcome::Ignored))
+    }
+
+    fn count(&self, f: impl Fn(&TestOutcome) -> bool) -> usize {
+        self.cases.iter().filter(|case| f(&case.outcome)).count()
+    }
+
+    /// Renders the suite as a JUnit XML document.
+    pub fn to_junit_xml(&self, name: &str) -> String {
+        let mut xml = String::new();
+        // writing to a String does not fail
+        let _ = self.write_junit_xml(&mut xml, name);
+        xml
+    }
+
+    fn write_junit_xml(&self, xml: &mut String, name: &str) -> fmt::Result {
+        writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(xml, "<testsuites>")?;
+        writeln!(
+            xml,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" errors="0" skipped="{}">"#,
+            Escaped(name),
+            self.cases.len(),
+            self.failures(),
+            self.skipped()
+        )?;
+        for case in &self.cases {
+            write!(
+                xml,
+                r#"    <testcase name="{}" classname="{}""#,
+                Escaped(&case.name),
+                Escaped(name)
+            )?;
+            match &case.outcome {
+                TestOutcome::Passed => writeln!(xml, "/>")?,
+                TestOutcome::Ignored => {
+                    writeln!(xml, ">")?;
+                    writeln!(xml, "      <skipped/>")?;
+                    writeln!(xml, "    </testcase>")?;
+                }
+                TestOutcome::Failed { output } => {
+                    writeln!(xml, ">")?;
+                    match output {
+                        Some(output) => writeln!(
+                            xml,
+                            r#"      <failure message="test failed">{}</failure>"#,
+                            Escaped(output)
+                        )?,
+                        None => writeln!(xml, r#"      <failure message="test failed"/>"#)?,
+                    }
+                    writeln!(xml, "    </testcase>")?;
+                }
+            }
+        }
+        writeln!(xml, "  </testsuite>")?;
+        writeln!(xml, "</testsuites>")
+    }
+}
+
+/// Parses the output of a libtest test binary.
+///
+/// Collects the `test <name> ... <result>` lines and attaches the sections of the
+/// `failures:` block (`---- <name> stdout ----`) to the failed tests. Lines that
+/// were not printed by libtest are ignored.
+pub fn parse_libtest_output(output: &str) -> TestSuite {
+    let mut suite = TestSuite::default();
+    let mut failure_outputs: Vec<(String, String)> = Vec::new();
+    let mut current_failure: Option<(String, Vec<&str>)> = None;
+    let mut in_failures = false;
+
+    for line in output.lines().map(|line| line.trim_end_matches('\r')) {
+        if in_failures {
+            if let Some(name) = parse_failure_header(line) {
+                if let Some(failure) = current_failure.take() {
+                    failure_outputs.push(finish_failure(failure));
+                }
+                current_failure = Some((name.to_string(), Vec::new()));
+                continue;
+            }
+            // the second `failures:` line starts the list of failed test names
+            if line == "failures:" || line.starts_with("test result:") {
+                if let Some(failure) = current_failure.take() {
+                    failure_outputs.push(finish_failure(failure));
+                }
+                in_failures = false;
+                continue;
+            }
+            if let Some((_, lines)) = current_failure.as_mut() {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        if line == "failures:" && suite.failures() > 0 && failure_outputs.is_empty() {
+            in_failures = true;
+        } else if let Some(case) = parse_test_line(line) {
+            suite.cases.push(case);
+        }
+    }
+    if let Some(failure) = current_failure.take() {
+        failure_outputs.push(finish_failure(failure));
+    }
+
+    for (name, failure_output) in failure_outputs {
+        if let Some(TestCase {
+            outcome: TestOutcome::Failed { output },
+            ..
+        }) = suite.cases.iter_mut().find(|case| case.name == name)
+        {
+            *output = Some(failure_output);
+        }
+    }
+    suite
+}
+
+fn parse_test_line(line: &str) -> Option<TestCase> {
+    let (name, result) = line.strip_prefix("test ")?.split_once(" ... ")?;
+    // `#[should_panic]` tests are printed as `test <name> - should panic ... ok`
+    let name = name.trim_end_matches(" - should panic");
+    let outcome = match result {
+        "ok" => TestOutcome::Passed,
+        "FAILED" => TestOutcome::Failed { output: None },
+        result if result.starts_with("ignored") => TestOutcome::Ignored,
+        result if result.starts_with("bench:") => TestOutcome::Passed,
+        _ => return None,
+    };
+    Some(TestCase {
+        name: name.to_string(),
+        outcome,
+    })
+}
+
+fn parse_failure_header(line: &str) -> Option<&str> {
+    line.strip_prefix("---- ")?.strip_suffix(" stdout ----")
+}
+
+fn finish_failure((name, lines): (String, Vec<&str>)) -> (String, String) {
+    (name, lines.join("\n").trim().to_string())
+}
+
+struct Escaped<'a>(&'a str);
+
+impl Display for Escaped<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&apos;")?,
+                // control characters are not allowed in XML 1.0
+                c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "
+running 4 tests
+test tests::test_add ... ok
+test tests::test_ignored ... ignored
+test tests::test_panic - should panic ... ok
+test tests::test_sub ... FAILED
+
+failures:
+
+---- tests::test_sub stdout ----
+thread 'tests::test_sub' panicked at 'assertion failed: `(left == right)`
+  left: `1`,
+ right: `2`', src/lib.rs:21:9
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    tests::test_sub
+
+test result: FAILED. 2 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+";
+
+    #[test]
+    fn test_parse_libtest_output() {
+        let suite = parse_libtest_output(OUTPUT);
+
+        assert_eq!(
+            suite.cases,
+            vec![
+                TestCase {
+                    name: "tests::test_add".to_string(),
+                    outcome: TestOutcome::Passed
+                },
+                TestCase {
+                    name: "tests::test_ignored".to_string(),
+                    outcome: TestOutcome::Ignored
+                },
+                TestCase {
+                    name: "tests::test_panic".to_string(),
+                    outcome: TestOutcome::Passed
+                },
+                TestCase {
+                    name: "tests::test_sub".to_string(),
+                    outcome: TestOutcome::Failed {
+                        output: Some(
+                            "thread 'tests::test_sub' panicked at 'assertion failed: `(left == right)`
+  left: `1`,
+ right: `2`', src/lib.rs:21:9
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"
+                                .to_string()
+                        )
+                    }
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_interleaved_output() {
+        let suite = parse_libtest_output(
+            "running 2 tests\r\nprinted by a test\r\ntest a ... ok\r\ntest b ... ignored, slow\r\nbench_x ... ok\r\n",
+        );
+
+        assert_eq!(
+            suite.cases,
+            vec![
+                TestCase {
+                    name: "a".to_string(),
+                    outcome: TestOutcome::Passed
+                },
+                TestCase {
+                    name: "b".to_string(),
+                    outcome: TestOutcome::Ignored
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_bench_output() {
+        let suite = parse_libtest_output(
+            "running 1 test\ntest bench_add ... bench:          12 ns/iter (+/- 1)\n",
+        );
+
+        assert_eq!(
+            suite.cases,
+            vec![TestCase {
+                name: "bench_add".to_string(),
+                outcome: TestOutcome::Passed
+            }]
+        );
+    }
+
+    #[test]
+    fn test_to_junit_xml() {
+        let xml = parse_libtest_output(OUTPUT).to_junit_xml("emulator-5554/integration-1234");
+
+        assert_eq!(
+            xml,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="emulator-5554/integration-1234" tests="4" failures="1" errors="0" skipped="1">
+    <testcase name="tests::test_add" classname="emulator-5554/integration-1234"/>
+    <testcase name="tests::test_ignored" classname="emulator-5554/integration-1234">
+      <skipped/>
+    </testcase>
+    <testcase name="tests::test_panic" classname="emulator-5554/integration-1234"/>
+    <testcase name="tests::test_sub" classname="emulator-5554/integration-1234">
+      <failure message="test failed">thread &apos;tests::test_sub&apos; panicked at &apos;assertion failed: `(left == right)`
+  left: `1`,
+ right: `2`&apos;, src/lib.rs:21:9
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::{common::table::write_table, TaiResult};
+
+mod event;
+pub mod junit;
+mod reporter;
+
+pub use event::{DeviceInfo, Event};
+pub(crate) use reporter::sanitize_file_name;
+pub use reporter::Reporter;
+
+/// The outcome of running a bundle on a device.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum RunStatus {
+    Passed,
+    /// The test binary exited with a non-zero exit code (if known).
+    Failed {
+        exit_code: Option<i32>,
+    },
+    /// The test binary was killed by a signal, e.g. a segfault.
+    Crashed {
+        signal: i32,
+    },
+    /// The test binary was killed because it exceeded its timeout.
+    TimedOut,
+    /// The bundle could not be installed or launched.
+    Error {
+        message: String,
+    },
+}
+
+impl RunStatus {
+    pub fn from_exit_code(exit_code: Option<i32>) -> Self {
+        match exit_code {
+            Some(0) => Self::Passed,
+            exit_code => Self::Failed { exit_code },
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Passed)
+    }
+}
+
+impl Display for RunStatus {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Passed => fmt.write_str("ok"),
+            Self::Failed {
+                exit_code: Some(code),
+            } => write!(fmt, "failed (exit code {})", code),
+            Self::Failed { exit_code: None } => fmt.write_str("failed"),
+            Self::Crashed { signal } => match signal_name(*signal) {
+                Some(name) => write!(fmt, "crashed (signal {}, {})", signal, name),
+                None => write!(fmt, "crashed (signal {})", signal),
+            },
+            Self::TimedOut => fmt.write_str("timed out"),
+            Self::Error { message } => write!(fmt, "error: {}", message),
+        }
+    }
+}
+
+/// The names of the signals that usually end a crashed test binary on Linux/Android.
+fn signal_name(signal: i32) -> Option<&'static str> {
+    match signal {
+        4 => Some("SIGILL"),
+        5 => Some("SIGTRAP"),
+        6 => Some("SIGABRT"),
+        7 => Some("SIGBUS"),
+        8 => Some("SIGFPE"),
+        9 => Some("SIGKILL"),
+        11 => Some("SIGSEGV"),
+        15 => Some("SIGTERM"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleResult {
+    pub device: String,
+    pub bundle: String,
+    #[serde(flatten)]
+    pub status: RunStatus,
+    /// Duration in seconds
+    #[serde(with = "duration_secs")]
+    pub duration: Duration,
+}
+
+impl BundleResult {
+    /// Runs `f` and records its outcome and how long it took.
+    pub fn measure<F>(device: &str, bundle: &str, f: F) -> Self
+    where
+        F: FnOnce() -> TaiResult<RunStatus>,
+    {
+        let start = Instant::now();
+        let status = f().unwrap_or_else(|err| RunStatus::Error {
+            message: format!("{:#}", err),
+        });
+        Self {
+            device: device.to_owned(),
+            bundle: bundle.to_owned(),
+            status,
+            duration: start.elapsed(),
+        }
+    }
+}
+
+/// A target that was not run because no device could run its bundles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedTarget {
+    pub target: String,
+    pub reason: String,
+}
+
+/// The results of all bundles that were run during a `cargo-tai` invocation.
+#[derive(Debug, Default)]
+pub struct RunReport {
+    pub results: Vec<BundleResult>,
+    pub skipped: Vec<SkippedTarget>,
+}
+
+impl RunReport {
+    pub fn push(&mut self, result: BundleResult) {
+        self.results.push(result);
+    }
+
+    pub fn skip(&mut self, skipped: SkippedTarget) {
+        self.skipped.push(skipped);
+    }
+
+    pub fn extend<I: IntoIterator<Item = BundleResult>>(&mut self, results: I) {
+        self.results.extend(results);
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| !result.status.is_success())
+            .count()
+    }
+
+    /// Fails if at least one bundle did not pass or if all targets were skipped.
+    pub fn into_result(self) -> TaiResult<()> {
+        if self.results.is_empty() && !self.skipped.is_empty() {
+            bail!(
+                "{}",
+                self.skipped
+                    .iter()
+                    .map(|skipped| skipped.reason.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        }
+        match self.failed() {
+            0 => Ok(()),
+            failed => bail!("{} of {} test runs failed", failed, self.results.len()),
+        }
+    }
+}
+
+impl Display for RunReport {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        const HEADER: [&str; 4] = ["device", "bundle", "status", "duration"];
+
+        let rows = self
+            .results
+            .iter()
+            .map(|result| {
+                vec![
+                    result.device.clone(),
+                    result.bundle.clone(),
+                    result.status.to_string(),
+                    format!("{:.2}s", result.duration.as_secs_f64()),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        writeln!(fmt)?;
+        write_table(fmt, &HEADER, &rows)?;
+        if !self.skipped.is_empty() {
+            writeln!(fmt)?;
+        }
+        for skipped in &self.skipped {
+            writeln!(fmt, "skipped {}: {}", skipped.target, skipped.reason)?;
+        }
+        write!(
+            fmt,
+            "\n{} passed; {} failed",
+            self.results.len() - self.failed(),
+            self.failed()
+        )?;
+        match self.skipped.len() {
+            0 => writeln!(fmt),
+            skipped => writeln!(fmt, "; {} skipped", skipped),
+        }
+    }
+}
+
+mod duration_secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        f64::deserialize(deserializer).map(Duration::from_secs_f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(device: &str, bundle: &str, status: RunStatus) -> BundleResult {
+        BundleResult {
+            device: device.to_string(),
+            bundle: bundle.to_string(),
+            status,
+            duration: Duration::from_millis(1500),
+        }
+    }
+
+    #[test]
+    fn test_report_table() {
+        let report = RunReport {
+            results: vec![
+                result("emulator-5554", "integration-1234", RunStatus::Passed),
+                result(
+                    "R58M123",
+                    "lib-abcd",
+                    RunStatus::Failed {
+                        exit_code: Some(101),
+                    },
+                ),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            report.to_string(),
+            "
+device         bundle            status                  duration
+emulator-5554  integration-1234  ok                      1.50s
+R58M123        lib-abcd          failed (exit code 101)  1.50s
+
+1 passed; 1 failed
+"
+        );
+    }
+
+    #[test]
+    fn test_report_skipped_targets() {
+        let mut report = RunReport::default();
+        report.skip(SkippedTarget {
+            target: "i686-linux-android".to_string(),
+            reason: "no android device available".to_string(),
+        });
+
+        assert_eq!(
+            report.to_string(),
+            "
+device  bundle  status  duration
+
+skipped i686-linux-android: no android device available
+
+0 passed; 0 failed; 1 skipped
+"
+        );
+        assert_eq!(
+            report.into_result().unwrap_err().to_string(),
+            "no android device available"
+        );
+    }
+
+    #[test]
+    fn test_status_display() {
+        assert_eq!(
+            RunStatus::Crashed { signal: 11 }.to_string(),
+            "crashed (signal 11, SIGSEGV)"
+        );
+        assert_eq!(
+            RunStatus::Crashed { signal: 31 }.to_string(),
+            "crashed (signal 31)"
+        );
+    }
+
+    #[test]
+    fn test_into_result() {
+        let mut report = RunReport::default();
+        report.push(result("emulator-5554", "lib-abcd", RunStatus::Passed));
+        assert!(report.into_result().is_ok());
+
+        let mut report = RunReport::default();
+        report.push(result("emulator-5554", "lib-abcd", RunStatus::Passed));
+        report.push(result(
+            "emulator-5554",
+            "integration-1234",
+            RunStatus::Error {
+                message: "failed to sync files".to_string(),
+            },
+        ));
+        assert!(report.into_result().is_err());
+    }
+
+    #[test]
+    fn test_measure() {
+        let result = BundleResult::measure("emulator-5554", "lib-abcd", || bail!("no space left"));
+        assert_eq!(
+            result.status,
+            RunStatus::Error {
+                message: "no space left".to_string()
+            }
+        );
+
+        let result = BundleResult::measure("emulator-5554", "lib-abcd", || {
+            Ok(RunStatus::from_exit_code(Some(0)))
+        });
+        assert!(result.status.is_success());
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context;
+use tracing::warn;
+
+use crate::{
+    common::{
+        opts::{MessageFormat, RunOptions},
+        project::ARTIFACTS_DIR,
+    },
+    TaiResult,
+};
+
+use super::{junit, Event, RunReport};
+
+/// Emits [`Event`]s to stdout (`--message-format json`) and/or to a report file (`--report`),
+/// writes the JUnit XML files (`--junit`) and knows where the artifacts are copied to (`--artifacts`).
+///
+/// Clones write to the same report file, so that the pipelines of all targets share it.
+#[derive(Clone)]
+pub struct Reporter {
+    message_format: MessageFormat,
+    report: Option<Arc<Mutex<BufWriter<File>>>>,
+    junit: Option<PathBuf>,
+    artifacts: Option<Artifacts>,
+}
+
+/// The directory of the bundles that is copied back from the devices and where it is copied to.
+#[derive(Clone)]
+struct Artifacts {
+    remote_dir: PathBuf,
+    local_root: PathBuf,
+}
+
+impl Reporter {
+    /// `tai_target` is the cargo-tai directory in the target directory of the project.
+    pub fn new(opts: &RunOptions, tai_target: &Path) -> TaiResult<Self> {
+        let report = opts
+            .report
+            .as_ref()
+            .map(|path| {
+                File::create(path)
+                    .map(|file| Arc::new(Mutex::new(BufWriter::new(file))))
+                    .with_context(|| format!("Failed to create report {}", path.display()))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            message_format: opts.message_format,
+            report,
+            junit: opts.junit.clone(),
+            artifacts: opts.artifacts.clone().map(|remote_dir| Artifacts {
+                remote_dir,
+                local_root: tai_target.join(ARTIFACTS_DIR),
+            }),
+        })
+    }
+
+    pub fn emit(&self, event: &Event) {
+        let json = match serde_json::to_string(event) {
+            Ok(json) => json,
+            Err(err) => {
+                warn!("failed to serialize event: {}", err);
+                return;
+            }
+        };
+
+        if let MessageFormat::Json = self.message_format {
+            println!("{}", json);
+        }
+
+        if let Some(report) = &self.report {
+            let mut report = report.lock().expect("report poisoned");
+            if let Err(err) = writeln!(report, "{}", json).and_then(|_| report.flush()) {
+                warn!("failed to write report: {}", err);
+            }
+        }
+    }
+
+    /// Parses the libtest output of a bundle and writes it as
+    /// `<junit dir>/<device>/<bundle>.xml`.
+    pub fn write_junit(&self, device: &str, bundle: &str, output: &[u8]) {
+        let junit_dir = match &self.junit {
+            Some(junit_dir) => junit_dir,
+            None => return,
+        };
+
+        let suite = junit::parse_libtest_output(&String::from_utf8_lossy(output));
+        let device_dir = junit_dir.join(sanitize_file_name(device));
+        let path = device_dir.join(format!("{}.xml", sanitize_file_name(bundle)));
+        let result = fs::create_dir_all(&device_dir)
+            .and_then(|_| fs::write(&path, suite.to_junit_xml(&format!("{}/{}", device, bundle))));
+        if let Err(err) = result {
+            warn!("failed to write {}: {}", path.display(), err);
+        }
+    }
+
+    /// Returns the directory of the bundle that is copied back from the device (`--artifacts`)
+    /// and `<cargo-tai target dir>/artifacts/<device>/<bundle>`, where it is copied to.
+    /// Artifacts of an earlier run are removed from the destination.
+    pub fn artifacts(&self, device: &str, bundle: &str) -> Option<(&Path, PathBuf)> {
+        let artifacts = self.artifacts.as_ref()?;
+        let local_dir = self.artifacts_dir(device, bundle)?;
+        let result = match local_dir.exists() {
+            true => fs::remove_dir_all(&local_dir),
+            false => Ok(()),
+        }
+        .and_then(|_| fs::create_dir_all(local_dir.parent().unwrap_or(&local_dir)));
+        match result {
+            Ok(_) => Some((&artifacts.remote_dir, local_dir)),
+            Err(err) => {
+                warn!("failed to prepare {}: {}", local_dir.display(), err);
+                None
+            }
+        }
+    }
+
+    /// Returns `<cargo-tai target dir>/artifacts/<device>/<bundle>`, where the artifacts of the
+    /// bundle are copied to, if `--artifacts` is used.
+    pub fn artifacts_dir(&self, device: &str, bundle: &str) -> Option<PathBuf> {
+        let artifacts = self.artifacts.as_ref()?;
+        Some(
+            artifacts
+                .local_root
+                .join(sanitize_file_name(device))
+                .join(sanitize_file_name(bundle)),
+        )
+    }
+
+    /// The output for humans, which is stderr if stdout is used for the JSON events.
+    pub fn human_output(&self) -> Box<dyn Write> {
+        match self.message_format {
+            MessageFormat::Human => Box::new(io::stdout()),
+            MessageFormat::Json => Box::new(io::stderr()),
+        }
+    }
+
+    /// Prints the summary of all runs and emits the final event.
+    pub fn finish(&self, report: &RunReport) {
+        let _ = write!(self.human_output(), "{}", report);
+        self.emit(&Event::RunFinished {
+            passed: report.results.len() - report.failed(),
+            failed: report.failed(),
+        });
+    }
+}
+
+/// Device ids like `192.168.0.2:5555` are not valid file names on every platform.
+pub(crate) fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    #[test]
+    fn test_write_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.json");
+        let reporter = Reporter::new(
+            &RunOptions {
+                report: Some(path.clone()),
+                ..Default::default()
+            },
+            dir.path(),
+        )
+        .unwrap();
+
+        reporter.emit(&Event::BundleCreated {
+            name: "lib-abcd".to_string(),
+            root: PathBuf::from("target/cargo-tai/lib-abcd"),
+        });
+        reporter.finish(&RunReport::default());
+
+        let events = fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Event>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                Event::BundleCreated {
+                    name: "lib-abcd".to_string(),
+                    root: PathBuf::from("target/cargo-tai/lib-abcd"),
+                },
+                Event::RunFinished {
+                    passed: 0,
+                    failed: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_junit() {
+        let dir = tempfile::tempdir().unwrap();
+        let reporter = Reporter::new(
+            &RunOptions {
+                junit: Some(dir.path().to_path_buf()),
+                ..Default::default()
+            },
+            dir.path(),
+        )
+        .unwrap();
+
+        reporter.write_junit(
+            "192.168.0.2:5555",
+            "integration-1234",
+            b"running 1 test\ntest a ... ok\n",
+        );
+
+        let xml =
+            fs::read_to_string(dir.path().join("192.168.0.2_5555/integration-1234.xml")).unwrap();
+        assert!(
+            xml.contains(r#"<testcase name="a" classname="192.168.0.2:5555/integration-1234"/>"#)
+        );
+    }
+
+    #[test]
+    fn test_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let reporter = Reporter::new(&RunOptions::default(), dir.path()).unwrap();
+        assert!(reporter
+            .artifacts("emulator-5554", "integration-1234")
+            .is_none());
+
+        let reporter = Reporter::new(
+            &RunOptions {
+                artifacts: Some(PathBuf::from("artifacts")),
+                ..Default::default()
+            },
+            dir.path(),
+        )
+        .unwrap();
+        let local_dir = dir
+            .path()
+            .join("artifacts/192.168.0.2_5555/integration-1234");
+        fs::create_dir_all(&local_dir).unwrap();
+        fs::write(local_dir.join("old.txt"), "").unwrap();
+
+        let (remote_dir, dest) = reporter
+            .artifacts("192.168.0.2:5555", "integration-1234")
+            .unwrap();
+        assert_eq!(remote_dir, Path::new("artifacts"));
+        assert_eq!(dest, local_dir);
+        assert!(!dest.exists());
+        assert!(dest.parent().unwrap().is_dir());
+    }
+}
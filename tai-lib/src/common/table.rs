@@ -0,0 +1,28 @@
+use std::fmt::{self, Write};
+
+/// Writes left-aligned columns that are separated by two spaces.
+pub fn write_table<W: Write, R: AsRef<[String]>>(
+    out: &mut W,
+    header: &[&str],
+    rows: &[R],
+) -> fmt::Result {
+    let mut widths = header.iter().map(|column| column.len()).collect::<Vec<_>>();
+    for row in rows {
+        for (width, column) in widths.iter_mut().zip(row.as_ref()) {
+            *width = (*width).max(column.len());
+        }
+    }
+
+    let header = header.iter().map(ToString::to_string).collect::<Vec<_>>();
+    for row in std::iter::once(header.as_slice()).chain(rows.iter().map(AsRef::as_ref)) {
+        let last = row.len().saturating_sub(1);
+        for (index, (column, width)) in row.iter().zip(&widths).enumerate() {
+            match index == last {
+                true => write!(out, "{}", column)?,
+                false => write!(out, "{:<width$}  ", column, width = width)?,
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
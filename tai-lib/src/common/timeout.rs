@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+
+use super::opts::{BinaryOptions, RunOptions};
+
+/// The time limits of the bundles that are run on a device.
+#[derive(Debug, Clone, Copy)]
+pub struct Timeouts {
+    bundle: Option<Duration>,
+    device_deadline: Option<Instant>,
+}
+
+impl Timeouts {
+    /// Starts the clock of `--device-timeout` for a device.
+    pub fn start(binary_opt: &BinaryOptions, run_opt: &RunOptions) -> Self {
+        Self {
+            bundle: binary_opt.timeout,
+            device_deadline: run_opt
+                .device_timeout
+                .map(|timeout| Instant::now() + timeout),
+        }
+    }
+
+    /// The timeout of the next bundle, which is `--timeout` limited by the time that is
+    /// left on the device.
+    pub fn next_bundle(&self) -> Option<Duration> {
+        let left_on_device = self
+            .device_deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        match (self.bundle, left_on_device) {
+            (Some(bundle), Some(left)) => Some(bundle.min(left)),
+            (bundle, left) => bundle.or(left),
+        }
+    }
+
+    /// Whether the time of the device is used up.
+    pub fn device_expired(&self) -> bool {
+        matches!(self.device_deadline, Some(deadline) if Instant::now() >= deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeouts(bundle: Option<u64>, device: Option<u64>) -> Timeouts {
+        Timeouts::start(
+            &BinaryOptions {
+                timeout: bundle.map(Duration::from_secs),
+                ..Default::default()
+            },
+            &RunOptions {
+                device_timeout: device.map(Duration::from_secs),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_next_bundle() {
+        assert_eq!(timeouts(None, None).next_bundle(), None);
+        assert_eq!(
+            timeouts(Some(10), None).next_bundle(),
+            Some(Duration::from_secs(10))
+        );
+        assert!(timeouts(None, Some(10)).next_bundle().unwrap() <= Duration::from_secs(10));
+        assert!(timeouts(Some(60), Some(10)).next_bundle().unwrap() <= Duration::from_secs(10));
+        assert!(!timeouts(Some(60), Some(10)).device_expired());
+        assert!(timeouts(None, Some(0)).device_expired());
+    }
+}
@@ -1,4 +1,10 @@
-use std::process::ExitStatus;
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
 
 use anyhow::{bail, Result};
 
@@ -14,3 +20,212 @@ impl ExitStatusExt for ExitStatus {
         }
     }
 }
+
+/// The stream on which a process wrote a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+pub trait CommandExt {
+    /// Like [`Command::output`], but calls `on_line` for every line of stdout and stderr
+    /// as soon as the process has written it. The line includes its line break (if any).
+    ///
+    /// Returns `None` if the process was killed because it did not exit within `timeout`.
+    fn output_streamed<F>(
+        &mut self,
+        timeout: Option<Duration>,
+        on_line: F,
+    ) -> io::Result<Option<Output>>
+    where
+        F: Fn(OutputStream, &[u8]) + Sync;
+}
+
+impl CommandExt for Command {
+    fn output_streamed<F>(
+        &mut self,
+        timeout: Option<Duration>,
+        on_line: F,
+    ) -> io::Result<Option<Output>>
+    where
+        F: Fn(OutputStream, &[u8]) + Sync,
+    {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut child = self.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        let (sender, receiver) = mpsc::channel();
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        // The readers are not joined: a descendant of the process can keep the pipes open
+        // after the process was killed, e.g. `debugserver` of `lldb`.
+        spawn_reader(stdout, OutputStream::Stdout, sender.clone());
+        spawn_reader(stderr, OutputStream::Stderr, sender);
+
+        let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+        let mut on_message = |message: ReaderMessage| match message {
+            ReaderMessage::Line(stream, line) => {
+                on_line(stream, &line);
+                match stream {
+                    OutputStream::Stdout => stdout.extend(line),
+                    OutputStream::Stderr => stderr.extend(line),
+                }
+                Ok(())
+            }
+            ReaderMessage::Error(err) => Err(err),
+        };
+
+        let mut status = None;
+        let mut readers_done = false;
+        loop {
+            if !readers_done {
+                match receiver.recv_timeout(POLL_INTERVAL) {
+                    Ok(message) => {
+                        if let Err(err) = on_message(message) {
+                            let _ = child.kill();
+                            child.wait()?;
+                            return Err(err);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => readers_done = true,
+                }
+            }
+            if status.is_none() {
+                status = child.try_wait()?;
+            }
+            let timed_out = matches!(deadline, Some(deadline) if Instant::now() >= deadline);
+            match status {
+                // after a timeout, a descendant still holds the pipes open
+                Some(status) if readers_done || timed_out => {
+                    receiver.try_iter().try_for_each(&mut on_message)?;
+                    return Ok(Some(Output {
+                        status,
+                        stdout,
+                        stderr,
+                    }));
+                }
+                None if timed_out => {
+                    child.kill()?;
+                    child.wait()?;
+                    receiver.try_iter().try_for_each(&mut on_message)?;
+                    return Ok(None);
+                }
+                None if readers_done => thread::sleep(POLL_INTERVAL),
+                _ => {}
+            }
+        }
+    }
+}
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub trait ChildExt {
+    /// Waits for the process to exit. If it is still running after `timeout`, the process
+    /// is killed and `None` is returned.
+    fn wait_timeout(&mut self, timeout: Option<Duration>) -> io::Result<Option<ExitStatus>>;
+}
+
+impl ChildExt for Child {
+    fn wait_timeout(&mut self, timeout: Option<Duration>) -> io::Result<Option<ExitStatus>> {
+        let deadline = match timeout {
+            Some(timeout) => Instant::now() + timeout,
+            None => return self.wait().map(Some),
+        };
+
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(Some(status));
+            }
+            if Instant::now() >= deadline {
+                self.kill()?;
+                self.wait()?;
+                return Ok(None);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+enum ReaderMessage {
+    Line(OutputStream, Vec<u8>),
+    Error(io::Error),
+}
+
+/// Sends each line of `reader` until EOF. The channel is disconnected when both readers end.
+fn spawn_reader<R>(reader: R, stream: OutputStream, sender: Sender<ReaderMessage>)
+where
+    R: Read + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        loop {
+            let mut line = Vec::new();
+            let message = match reader.read_until(b'\n', &mut line) {
+                Ok(0) => return,
+                Ok(_) => ReaderMessage::Line(stream, line),
+                Err(err) => ReaderMessage::Error(err),
+            };
+            let failed = matches!(message, ReaderMessage::Error(_));
+            if sender.send(message).is_err() || failed {
+                return;
+            }
+        }
+    });
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn test_output_streamed() {
+        let lines = Mutex::new(Vec::new());
+        let output = Command::new("sh")
+            .args(["-c", "echo out1; echo err1 >&2; printf out2; exit 3"])
+            .output_streamed(None, |stream, line| {
+                lines.lock().unwrap().push((stream, line.to_vec()));
+            })
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stdout, b"out1\nout2");
+        assert_eq!(output.stderr, b"err1\n");
+
+        let lines = lines.into_inner().unwrap();
+        let stdout_lines = lines
+            .iter()
+            .filter(|(stream, _)| *stream == OutputStream::Stdout)
+            .map(|(_, line)| line.as_slice())
+            .collect::<Vec<_>>();
+        assert_eq!(stdout_lines, vec![&b"out1\n"[..], &b"out2"[..]]);
+        assert!(lines.contains(&(OutputStream::Stderr, b"err1\n".to_vec())));
+    }
+
+    #[test]
+    fn test_output_streamed_timeout() {
+        let start = Instant::now();
+        let output = Command::new("sh")
+            .args(["-c", "echo started; exec sleep 10"])
+            .output_streamed(Some(Duration::from_millis(200)), |_, _| {})
+            .unwrap();
+
+        assert!(output.is_none());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_output_streamed_timeout_with_descendant() {
+        // the background `sleep` keeps stdout and stderr open after `sh` was killed
+        let start = Instant::now();
+        let output = Command::new("sh")
+            .args(["-c", "sleep 60 & sleep 60"])
+            .output_streamed(Some(Duration::from_millis(200)), |_, _| {})
+            .unwrap();
+
+        assert!(output.is_none());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    fs::File,
+    io::{self, ErrorKind, Read},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Follows a file that is written by another process (like `tail -f`) while `f` is running.
+///
+/// Every chunk that is appended to the file is passed to `on_output`. The file does not
+/// need to exist when `f` is started. Returns the result of `f` and a copy of everything
+/// that was read from the file.
+pub fn follow_file_while<T, F, O>(path: &Path, mut on_output: O, f: F) -> (T, io::Result<Vec<u8>>)
+where
+    F: FnOnce() -> T,
+    O: FnMut(&[u8]) + Send,
+{
+    let done = AtomicBool::new(false);
+    thread::scope(|scope| {
+        let follower = scope.spawn(|| {
+            let mut file = None;
+            let mut content = Vec::new();
+            loop {
+                // read once more after `f` has returned to get the rest of the file
+                let finished = done.load(Ordering::SeqCst);
+                if file.is_none() {
+                    file = match File::open(path) {
+                        Ok(opened) => Some(opened),
+                        Err(err) if err.kind() == ErrorKind::NotFound => None,
+                        Err(err) => return Err(err),
+                    };
+                }
+                if let Some(file) = file.as_mut() {
+                    let start = content.len();
+                    file.read_to_end(&mut content)?;
+                    if content.len() > start {
+                        on_output(&content[start..]);
+                    }
+                }
+                if finished {
+                    return Ok(content);
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+
+        let result = f();
+        done.store(true, Ordering::SeqCst);
+        (result, follower.join().expect("file follower panicked"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write, sync::Mutex};
+
+    use super::*;
+
+    #[test]
+    fn test_follow_file_while() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stdout");
+        let chunks = Mutex::new(Vec::new());
+
+        let (chunks_while_running, content) = follow_file_while(
+            &path,
+            |chunk| chunks.lock().unwrap().push(chunk.to_vec()),
+            || {
+                thread::sleep(POLL_INTERVAL);
+                let mut file = File::create(&path).unwrap();
+                file.write_all(b"running 1 test\n").unwrap();
+                thread::sleep(POLL_INTERVAL * 3);
+                let chunks_while_running = chunks.lock().unwrap().len();
+                file.write_all(b"test a ... ok\n").unwrap();
+                chunks_while_running
+            },
+        );
+
+        assert_eq!(chunks_while_running, 1);
+        assert_eq!(content.unwrap(), b"running 1 test\ntest a ... ok\n");
+        assert_eq!(
+            chunks.into_inner().unwrap().concat(),
+            b"running 1 test\ntest a ... ok\n"
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
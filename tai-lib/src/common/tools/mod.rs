@@ -4,6 +4,7 @@ use cargo_metadata::{Metadata, MetadataCommand};
 
 use crate::TaiResult;
 pub mod command_ext;
+pub mod follow;
 pub mod rsync;
 
 pub use rsync::Rsync;
@@ -11,7 +11,9 @@ use tracing::{debug, instrument};
 use crate::{
     common::{
         bundle::{copy_resources, BuiltBundle},
+        command::Platform,
         compiler::BuiltUnit,
+        opts::Resource,
     },
     TaiResult,
 };
@@ -23,7 +25,7 @@ const INFO_PLIST: &str = "Info.plist";
 pub fn create_bundle<P: AsRef<Path>>(
     unit: BuiltUnit,
     bundles_root: P,
-    resources: &Option<Vec<(String, PathBuf)>>,
+    resources: &Option<Vec<Resource>>,
     app_id: &str,
 ) -> TaiResult<BuiltBundle> {
     let version_root = bundles_root
@@ -51,7 +53,7 @@ pub fn create_bundle<P: AsRef<Path>>(
         .with_context(|| format!("Failed to create {}", INFO_PLIST))?;
 
     if let Some(resources) = resources {
-        copy_resources(&bundle_root, resources)?;
+        copy_resources(&bundle_root, &unit.target_name, resources)?;
     }
 
     Ok(BuiltBundle {
@@ -102,10 +104,28 @@ fn create_plist<P: AsRef<Path>>(
     Ok(path)
 }
 
+/// The architecture of `UIRequiredDeviceCapabilities`, which is the one of the device or
+/// simulator that runs the app.
 fn to_apple_arch(target: &TargetInfo) -> TaiResult<&'static str> {
-    match target.arch {
-        Arch::aarch64 => Ok("arm64"),
-        Arch::x86_64 => Ok("x86_64"),
-        _ => bail!("unknown target"),
+    match (Platform::of(target), target.arch) {
+        (Some(Platform::IosDevice | Platform::IosSimulator), Arch::aarch64) => Ok("arm64"),
+        (Some(Platform::IosSimulator), Arch::x86_64) => Ok("x86_64"),
+        _ => bail!("unsupported target: {}", target.triple),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cfg_expr::targets::get_builtin_target_by_triple;
+
+    use super::*;
+
+    #[test]
+    fn test_to_apple_arch() {
+        let apple_arch = |triple| to_apple_arch(get_builtin_target_by_triple(triple).unwrap());
+        assert_eq!(apple_arch("aarch64-apple-ios").unwrap(), "arm64");
+        assert_eq!(apple_arch("aarch64-apple-ios-sim").unwrap(), "arm64");
+        assert_eq!(apple_arch("x86_64-apple-ios").unwrap(), "x86_64");
+        assert!(apple_arch("aarch64-linux-android").is_err());
     }
 }
@@ -1,4 +1,90 @@
+use tracing::warn;
+
+use crate::{
+    common::devices::ReachableDevice,
+    ios::tools::{libimobiledevice, xcrun},
+    TaiResult,
+};
+
 pub mod physical;
 pub mod simulator;
 
 pub const APP_ID: &str = "cargo-tai";
+
+/// Lists the booted simulators and the connected devices. A source that fails, e.g. because
+/// libimobiledevice is not installed, is skipped with a warning.
+pub fn list_devices() -> Vec<ReachableDevice> {
+    let mut devices = Vec::new();
+    match list_simulators() {
+        Ok(simulators) => devices.extend(simulators),
+        Err(err) => warn!("failed to list iOS simulators: {:#}", err),
+    }
+    match list_physical_devices() {
+        Ok(physical_devices) => devices.extend(physical_devices),
+        Err(err) => warn!("failed to list iOS devices: {:#}", err),
+    }
+    devices
+}
+
+fn list_simulators() -> TaiResult<Vec<ReachableDevice>> {
+    let runtimes = xcrun::booted_simulator_runtimes().unwrap_or_else(|err| {
+        warn!(
+            "failed to read the iOS versions of the simulators: {:#}",
+            err
+        );
+        Default::default()
+    });
+    let simulators = xcrun::list_booted_simulators()?
+        .into_iter()
+        .map(|simulator| ReachableDevice {
+            platform: "ios-simulator",
+            os_version: runtimes.get(&simulator.udid).cloned(),
+            id: simulator.udid.clone(),
+            name: Some(simulator.name.clone()),
+            arch: std::env::consts::ARCH.to_string(),
+            targets: simulator_targets(std::env::consts::ARCH),
+        })
+        .collect();
+    Ok(simulators)
+}
+
+fn list_physical_devices() -> TaiResult<Vec<ReachableDevice>> {
+    let devices = libimobiledevice::list_devices()?
+        .into_iter()
+        .map(|device| ReachableDevice {
+            platform: "ios",
+            targets: match device.arch {
+                libimobiledevice::CpuArch::Aarch64 => vec!["aarch64-apple-ios"],
+                libimobiledevice::CpuArch::Unsupported(_) => vec![],
+            },
+            arch: device.arch.as_str().to_string(),
+            os_version: Some(format!("iOS {}", device.version)),
+            id: device.id,
+            name: Some(device.name),
+        })
+        .collect();
+    Ok(devices)
+}
+
+/// The targets that the simulators of a Mac with the architecture `host_arch` can run.
+/// Apple silicon runs `x86_64` apps in the simulator with Rosetta.
+fn simulator_targets(host_arch: &str) -> Vec<&'static str> {
+    match host_arch {
+        "aarch64" => vec!["aarch64-apple-ios-sim", "x86_64-apple-ios"],
+        _ => vec!["x86_64-apple-ios"],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulator_targets() {
+        assert_eq!(
+            simulator_targets("aarch64"),
+            vec!["aarch64-apple-ios-sim", "x86_64-apple-ios"]
+        );
+        assert_eq!(simulator_targets("x86_64"), vec!["x86_64-apple-ios"]);
+    }
+}
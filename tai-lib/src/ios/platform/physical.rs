@@ -1,10 +1,7 @@
 use crate::{
     common::{
-        opts::Options,
-        task::{
-            context::Context, get_project_metadata::GetProjectMetadata, set_bench_arg::SetBenchArg,
-            Runner,
-        },
+        report::RunReport,
+        task::{context::Context, set_bench_arg::SetBenchArg, Runner},
     },
     ios::task::{
         BuildBuiltUnits, CreateSignedBundles, ListPhysicalDevices, ReadSigningSettings,
@@ -13,18 +10,17 @@ use crate::{
     TaiResult,
 };
 
-pub fn run_command(requested: Options) -> TaiResult<()> {
-    Runner::execute(
+pub fn run_command(context: Context) -> TaiResult<RunReport> {
+    let mut context = Runner::execute(
         &[
-            Task::GetProjectMetadata(GetProjectMetadata),
+            Task::ListPhysicalDevices(ListPhysicalDevices),
             Task::SetBenchArg(SetBenchArg),
             Task::BuildBuiltUnits(BuildBuiltUnits),
-            Task::ListPhysicalDevices(ListPhysicalDevices),
             Task::ReadSigningSettings(ReadSigningSettings),
             Task::CreateSignedBundles(CreateSignedBundles),
             Task::RunOnPhysicalDevice(RunOnPhysicalDevice),
         ],
-        Context::from(requested),
+        context,
     )?;
-    Ok(())
+    Ok(context.remove())
 }
@@ -1,26 +1,361 @@
+use anyhow::bail;
+use tracing::{info, warn};
+
 use crate::{
     common::{
-        opts::Options,
+        opts::{Options, SimulatorOptions},
+        report::RunReport,
+        task::{context::Context, set_bench_arg::SetBenchArg, Runner},
+    },
+    ios::{
         task::{
-            context::Context, get_project_metadata::GetProjectMetadata, set_bench_arg::SetBenchArg,
-            Runner,
+            BuildBuiltUnits, CreateBundles, ListSimulators, RequestedSimulator, RunOnSimulators,
+            Task,
         },
+        tools::xcrun::{self, DeviceType, Runtime, SimulatorDevice, SimulatorList},
     },
-    ios::task::{BuildBuiltUnits, CreateBundles, ListSimulators, RunOnSimulators, Task},
     TaiResult,
 };
 
-pub fn run_command(requested: Options) -> TaiResult<()> {
-    Runner::execute(
+const IOS_RUNTIME_PREFIX: &str = "com.apple.CoreSimulator.SimRuntime.iOS";
+const BOOTED: &str = "Booted";
+
+pub fn run_command(mut context: Context) -> TaiResult<RunReport> {
+    let simulator = match &context.get::<Options>().devices.simulator {
+        Some(opts) => Some((prepare_simulator(opts)?, opts.clone())),
+        None => None,
+    };
+    context.insert(RequestedSimulator(simulator.as_ref().and_then(
+        |(prepared, opts)| opts.spec.as_ref().map(|_| prepared.udid.clone()),
+    )));
+
+    let result = Runner::execute(
         &[
-            Task::GetProjectMetadata(GetProjectMetadata),
+            Task::ListSimulators(ListSimulators),
             Task::SetBenchArg(SetBenchArg),
             Task::BuildBuiltUnits(BuildBuiltUnits),
-            Task::ListSimulators(ListSimulators),
             Task::CreateBundles(CreateBundles),
             Task::RunOnSimulators(RunOnSimulators),
         ],
-        Context::from(requested),
-    )?;
-    Ok(())
+        context,
+    );
+
+    if let Some((prepared, opts)) = simulator {
+        release_simulator(&prepared, &opts);
+    }
+    Ok(result?.remove())
+}
+
+/// A simulator that was prepared for the run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PreparedSimulator {
+    udid: String,
+    /// Booted by cargo-tai
+    booted: bool,
+    /// Created by cargo-tai
+    created: bool,
+}
+
+/// What needs to be done to get a simulator for the run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Plan {
+    /// A (matching) simulator is already booted
+    Use(String),
+    Boot(String),
+    Create {
+        name: String,
+        device_type: String,
+        runtime: String,
+    },
+}
+
+fn prepare_simulator(opts: &SimulatorOptions) -> TaiResult<PreparedSimulator> {
+    let list = xcrun::list_simulators()?;
+    let (udid, created) = match plan(&list, opts)? {
+        Plan::Use(udid) => {
+            return Ok(PreparedSimulator {
+                udid,
+                booted: false,
+                created: false,
+            })
+        }
+        Plan::Boot(udid) => (udid, false),
+        Plan::Create {
+            name,
+            device_type,
+            runtime,
+        } => {
+            info!("create simulator {}", name);
+            (
+                xcrun::create_simulator(&name, &device_type, &runtime)?,
+                true,
+            )
+        }
+    };
+    info!("boot simulator {}", udid);
+    let prepared = PreparedSimulator {
+        udid,
+        booted: true,
+        created,
+    };
+    if let Err(err) = xcrun::boot_simulator(&prepared.udid) {
+        release_simulator(&prepared, opts);
+        return Err(err);
+    }
+    Ok(prepared)
+}
+
+/// Shuts down and deletes the simulator as requested. Failures are only logged, so that
+/// they do not hide the result of the run.
+fn release_simulator(prepared: &PreparedSimulator, opts: &SimulatorOptions) {
+    if prepared.booted && (opts.shutdown || opts.ephemeral) {
+        info!("shut down simulator {}", prepared.udid);
+        if let Err(err) = xcrun::shutdown_simulator(&prepared.udid) {
+            warn!("failed to shut down simulator {}: {:#}", prepared.udid, err);
+        }
+    }
+    if prepared.created && opts.ephemeral {
+        info!("delete simulator {}", prepared.udid);
+        if let Err(err) = xcrun::delete_simulator(&prepared.udid) {
+            warn!("failed to delete simulator {}: {:#}", prepared.udid, err);
+        }
+    }
+}
+
+fn plan(list: &SimulatorList, opts: &SimulatorOptions) -> TaiResult<Plan> {
+    let spec = match &opts.spec {
+        Some(spec) => spec,
+        None if opts.ephemeral => bail!("an ephemeral simulator requires a device type"),
+        None => return plan_boot(list),
+    };
+    let (device_type, runtime) = match spec.rsplit_once('/') {
+        Some((device_type, runtime)) => (device_type.trim(), Some(runtime.trim())),
+        None => (spec.trim(), None),
+    };
+    let device_type = find_device_type(&list.devicetypes, device_type)?;
+    let runtime = find_runtime(&list.runtimes, runtime)?;
+
+    if !opts.ephemeral {
+        let matching = list
+            .devices
+            .get(&runtime.identifier)
+            .into_iter()
+            .flatten()
+            .filter(|sim| sim.is_available && sim.device_type_identifier == device_type.identifier)
+            .collect::<Vec<_>>();
+        if let Some(plan) = use_or_boot(&matching) {
+            return Ok(plan);
+        }
+    }
+
+    Ok(Plan::Create {
+        name: format!("cargo-tai {} ({})", device_type.name, runtime.name),
+        device_type: device_type.identifier.clone(),
+        runtime: runtime.identifier.clone(),
+    })
+}
+
+/// `--boot` without a device type: boots an available iOS simulator of the newest runtime
+/// if none is booted.
+fn plan_boot(list: &SimulatorList) -> TaiResult<Plan> {
+    let mut runtimes = list
+        .devices
+        .iter()
+        .filter_map(|(runtime, simulators)| {
+            let version = runtime.strip_prefix(IOS_RUNTIME_PREFIX)?.replace('-', ".");
+            Some((version_key(version.trim_start_matches('.')), simulators))
+        })
+        .collect::<Vec<_>>();
+    runtimes.sort_by(|(a, _), (b, _)| b.cmp(a));
+    let simulators = runtimes
+        .into_iter()
+        .flat_map(|(_, simulators)| simulators)
+        .filter(|sim| sim.is_available)
+        .collect::<Vec<_>>();
+
+    match use_or_boot(&simulators) {
+        Some(plan) => Ok(plan),
+        None => bail!(
+            "no iOS simulator available to boot, \
+            use `--simulator <device type>/<runtime>` to create one"
+        ),
+    }
+}
+
+/// Uses the first booted simulator or boots the first one.
+fn use_or_boot(simulators: &[&SimulatorDevice]) -> Option<Plan> {
+    match simulators.iter().find(|sim| sim.state == BOOTED) {
+        Some(sim) => Some(Plan::Use(sim.udid.clone())),
+        None => simulators.first().map(|sim| Plan::Boot(sim.udid.clone())),
+    }
+}
+
+/// Finds a device type by its name or identifier.
+fn find_device_type<'a>(device_types: &'a [DeviceType], name: &str) -> TaiResult<&'a DeviceType> {
+    match device_types
+        .iter()
+        .find(|device_type| device_type.name == name || device_type.identifier == name)
+    {
+        Some(device_type) => Ok(device_type),
+        None => bail!(
+            "unknown simulator device type `{}`, available: {}",
+            name,
+            device_types
+                .iter()
+                .map(|device_type| device_type.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Finds an available iOS runtime by its name (`iOS 16.2`), identifier or version (`16.2`).
+/// Without a name, the newest runtime is returned.
+fn find_runtime<'a>(runtimes: &'a [Runtime], name: Option<&str>) -> TaiResult<&'a Runtime> {
+    let mut available = runtimes
+        .iter()
+        .filter(|runtime| {
+            runtime.is_available && runtime.identifier.starts_with(IOS_RUNTIME_PREFIX)
+        })
+        .collect::<Vec<_>>();
+    available.sort_by_key(|runtime| version_key(&runtime.version));
+
+    let runtime = match name {
+        Some(name) => available.iter().find(|runtime| {
+            runtime.name == name || runtime.identifier == name || runtime.version == name
+        }),
+        None => available.last(),
+    };
+    match runtime {
+        Some(runtime) => Ok(runtime),
+        None => bail!(
+            "unknown iOS simulator runtime `{}`, available: {}",
+            name.unwrap_or_default(),
+            available
+                .iter()
+                .map(|runtime| runtime.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// `16.2` -> `[16, 2]`
+fn version_key(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST: &str = r#"{
+  "devicetypes": [
+    { "name": "iPhone 14", "identifier": "com.apple.CoreSimulator.SimDeviceType.iPhone-14" },
+    { "name": "iPad Air (5th generation)", "identifier": "com.apple.CoreSimulator.SimDeviceType.iPad-Air-5th-generation" }
+  ],
+  "runtimes": [
+    { "name": "iOS 16.2", "identifier": "com.apple.CoreSimulator.SimRuntime.iOS-16-2", "version": "16.2", "isAvailable": true },
+    { "name": "iOS 15.5", "identifier": "com.apple.CoreSimulator.SimRuntime.iOS-15-5", "version": "15.5", "isAvailable": true },
+    { "name": "watchOS 9.1", "identifier": "com.apple.CoreSimulator.SimRuntime.watchOS-9-1", "version": "9.1", "isAvailable": true }
+  ],
+  "devices": {
+    "com.apple.CoreSimulator.SimRuntime.iOS-16-2": [
+      { "udid": "A", "state": "Shutdown", "isAvailable": true, "deviceTypeIdentifier": "com.apple.CoreSimulator.SimDeviceType.iPhone-14" },
+      { "udid": "B", "state": "Booted", "isAvailable": true, "deviceTypeIdentifier": "com.apple.CoreSimulator.SimDeviceType.iPhone-14" }
+    ],
+    "com.apple.CoreSimulator.SimRuntime.iOS-15-5": [
+      { "udid": "C", "state": "Shutdown", "isAvailable": true, "deviceTypeIdentifier": "com.apple.CoreSimulator.SimDeviceType.iPhone-14" }
+    ]
+  }
+}"#;
+
+    fn list() -> SimulatorList {
+        serde_json::from_str(LIST).unwrap()
+    }
+
+    fn simulator(spec: Option<&str>, ephemeral: bool) -> SimulatorOptions {
+        SimulatorOptions {
+            spec: spec.map(ToString::to_string),
+            ephemeral,
+            shutdown: false,
+        }
+    }
+
+    #[test]
+    fn test_plan_uses_or_boots_matching_simulator() {
+        let list = list();
+        assert_eq!(
+            plan(&list, &simulator(Some("iPhone 14/iOS 16.2"), false)).unwrap(),
+            Plan::Use("B".to_string())
+        );
+        assert_eq!(
+            plan(&list, &simulator(Some("iPhone 14/15.5"), false)).unwrap(),
+            Plan::Boot("C".to_string())
+        );
+        // the newest runtime
+        assert_eq!(
+            plan(&list, &simulator(Some("iPhone 14"), false)).unwrap(),
+            Plan::Use("B".to_string())
+        );
+    }
+
+    #[test]
+    fn test_plan_creates_simulator() {
+        let list = list();
+        let create = Plan::Create {
+            name: "cargo-tai iPad Air (5th generation) (iOS 16.2)".to_string(),
+            device_type: "com.apple.CoreSimulator.SimDeviceType.iPad-Air-5th-generation"
+                .to_string(),
+            runtime: "com.apple.CoreSimulator.SimRuntime.iOS-16-2".to_string(),
+        };
+        assert_eq!(
+            plan(&list, &simulator(Some("iPad Air (5th generation)"), false)).unwrap(),
+            create
+        );
+        assert!(matches!(
+            plan(&list, &simulator(Some("iPhone 14/iOS 16.2"), true)).unwrap(),
+            Plan::Create { .. }
+        ));
+    }
+
+    #[test]
+    fn test_plan_validates_names() {
+        let list = list();
+        let err = plan(&list, &simulator(Some("iPhone 99"), false)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown simulator device type `iPhone 99`, available: iPhone 14, iPad Air (5th generation)"
+        );
+        let err = plan(&list, &simulator(Some("iPhone 14/watchOS 9.1"), false)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown iOS simulator runtime `watchOS 9.1`, available: iOS 15.5, iOS 16.2"
+        );
+        assert!(plan(&list, &simulator(None, true)).is_err());
+    }
+
+    #[test]
+    fn test_plan_boot() {
+        let mut list = list();
+        assert_eq!(
+            plan(&list, &simulator(None, false)).unwrap(),
+            Plan::Use("B".to_string())
+        );
+
+        list.devices
+            .values_mut()
+            .flatten()
+            .for_each(|sim| sim.state = "Shutdown".to_string());
+        assert_eq!(
+            plan(&list, &simulator(None, false)).unwrap(),
+            Plan::Boot("A".to_string())
+        );
+
+        list.devices.clear();
+        assert!(plan(&list, &simulator(None, false)).is_err());
+    }
 }
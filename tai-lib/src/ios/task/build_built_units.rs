@@ -5,6 +5,7 @@ use crate::{
         command::Command,
         compiler::{compile_benches, compile_tests, BuiltUnit},
         opts::Options,
+        report::{Event, Reporter},
         task::Task,
     },
     ios::compiler::{bench_command, benches_command, test_command, tests_command},
@@ -33,6 +34,11 @@ impl Task<Context> for BuildBuiltUnits {
             Command::Test | Command::Tests => compile_tests(cmd, &opts.compiler)?,
         };
 
+        let reporter: &Reporter = context.get();
+        built_units
+            .iter()
+            .for_each(|unit| reporter.emit(&Event::build_artifact(unit)));
+
         context.insert(BuiltUnits(built_units));
         Ok(context)
     }
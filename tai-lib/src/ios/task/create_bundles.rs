@@ -1,7 +1,13 @@
 use tracing::instrument;
 
 use crate::{
-    common::{bundle::create_bundles, opts::Options, project::ProjectMetadata, task::Task},
+    common::{
+        bundle::create_bundles,
+        opts::Options,
+        project::ProjectMetadata,
+        report::{Event, Reporter},
+        task::Task,
+    },
     ios::{bundle::bundler::create_bundle, platform::APP_ID},
     TaiResult,
 };
@@ -21,6 +27,12 @@ impl Task<Context> for CreateBundles {
             create_bundle(unit, root, resources, APP_ID)
         })?;
 
+        let reporter: &Reporter = context.get();
+        bundles
+            .bundles
+            .iter()
+            .for_each(|bundle| reporter.emit(&Event::bundle_created(bundle)));
+
         context.insert(bundles);
 
         Ok(context)
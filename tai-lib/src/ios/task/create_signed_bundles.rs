@@ -5,6 +5,7 @@ use crate::{
         bundle::{create_bundles, BuiltBundles},
         opts::Options,
         project::ProjectMetadata,
+        report::{Event, Reporter},
         task::Task,
     },
     ios::bundle::{
@@ -42,6 +43,12 @@ impl Task<Context> for CreateSignedBundles {
             .iter()
             .try_for_each(|bundle| sign_bundle(bundle, sig_settings, &entitlements))?;
 
+        let reporter: &Reporter = context.get();
+        bundles
+            .bundles
+            .iter()
+            .for_each(|bundle| reporter.emit(&Event::bundle_created(bundle)));
+
         context.insert(SignedBuiltBundles(bundles));
         Ok(context)
     }
@@ -1,7 +1,15 @@
-use anyhow::bail;
 use tracing::instrument;
 
-use crate::{common::task::Task, ios::tools::libimobiledevice, TaiResult};
+use crate::{
+    common::{
+        device_selection::{select_devices, DeviceProperties, NoDeviceAvailable},
+        opts::Options,
+        report::{DeviceInfo, Event, Reporter},
+        task::Task,
+    },
+    ios::tools::libimobiledevice,
+    TaiResult,
+};
 
 use super::Context;
 
@@ -14,8 +22,26 @@ impl Task<Context> for ListPhysicalDevices {
     fn run(&self, mut context: Context) -> TaiResult<Context> {
         let devices = libimobiledevice::list_devices()?;
         if devices.is_empty() {
-            bail!("no iOS device available");
+            return Err(NoDeviceAvailable("no iOS device available".to_string()).into());
         }
+        let devices = select_devices(devices, &context.get::<Options>().devices, |device| {
+            DeviceProperties {
+                id: &device.id,
+                name: Some(&device.name),
+                ..Default::default()
+            }
+        })?;
+
+        context.get::<Reporter>().emit(&Event::DevicesSelected {
+            devices: devices
+                .iter()
+                .map(|device| DeviceInfo {
+                    id: device.id.clone(),
+                    name: Some(device.name.clone()),
+                    arch: Some(device.arch.as_str().to_string()),
+                })
+                .collect(),
+        });
 
         context.insert(PhysicalDevices(devices));
         Ok(context)
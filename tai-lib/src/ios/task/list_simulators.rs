@@ -1,20 +1,54 @@
-use anyhow::bail;
 use tracing::instrument;
 
-use crate::{common::task::Task, ios::tools::xcrun, TaiResult};
+use crate::{
+    common::{
+        device_selection::{select_devices, DeviceProperties, NoDeviceAvailable},
+        opts::Options,
+        report::{DeviceInfo, Event, Reporter},
+        task::Task,
+    },
+    ios::tools::xcrun,
+    TaiResult,
+};
 
 use super::Context;
 pub struct Simulators(pub Vec<simctl::Device>);
 
+/// The UDID of the simulator that was booted or created for `--simulator`. Only this
+/// simulator is used if it is set.
+pub struct RequestedSimulator(pub Option<String>);
+
 pub struct ListSimulators;
 
 impl Task<Context> for ListSimulators {
     #[instrument(name = "list_simulators", skip(self, context))]
     fn run(&self, mut context: Context) -> TaiResult<Context> {
-        let simulators = xcrun::list_booted_simulators()?;
+        let mut simulators = xcrun::list_booted_simulators()?;
+        if let RequestedSimulator(Some(udid)) = context.get() {
+            simulators.retain(|simulator| &simulator.udid == udid);
+        }
         if simulators.is_empty() {
-            bail!("no iOS simulator available")
+            return Err(NoDeviceAvailable("no iOS simulator available".to_string()).into());
         }
+        let simulators =
+            select_devices(simulators, &context.get::<Options>().devices, |simulator| {
+                DeviceProperties {
+                    id: &simulator.udid,
+                    name: Some(&simulator.name),
+                    ..Default::default()
+                }
+            })?;
+
+        context.get::<Reporter>().emit(&Event::DevicesSelected {
+            devices: simulators
+                .iter()
+                .map(|simulator| DeviceInfo {
+                    id: simulator.udid.clone(),
+                    name: Some(simulator.name.clone()),
+                    arch: None,
+                })
+                .collect(),
+        });
 
         context.insert(Simulators(simulators));
         Ok(context)
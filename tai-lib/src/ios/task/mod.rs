@@ -1,7 +1,5 @@
 use crate::{
-    common::task::{
-        context::Context, get_project_metadata::GetProjectMetadata, set_bench_arg::SetBenchArg,
-    },
+    common::task::{context::Context, set_bench_arg::SetBenchArg},
     TaiResult,
 };
 
@@ -15,10 +13,14 @@ mod run_on_physical_device;
 mod run_on_simulators;
 
 pub use self::{
-    build_built_units::BuildBuiltUnits, create_bundles::CreateBundles,
-    create_signed_bundles::CreateSignedBundles, list_physical_devices::ListPhysicalDevices,
-    list_simulators::ListSimulators, read_signing_settings::ReadSigningSettings,
-    run_on_physical_device::RunOnPhysicalDevice, run_on_simulators::RunOnSimulators,
+    build_built_units::BuildBuiltUnits,
+    create_bundles::CreateBundles,
+    create_signed_bundles::CreateSignedBundles,
+    list_physical_devices::ListPhysicalDevices,
+    list_simulators::{ListSimulators, RequestedSimulator},
+    read_signing_settings::ReadSigningSettings,
+    run_on_physical_device::RunOnPhysicalDevice,
+    run_on_simulators::RunOnSimulators,
 };
 
 pub enum Task {
@@ -30,7 +32,6 @@ pub enum Task {
     CreateSignedBundles(CreateSignedBundles),
     RunOnPhysicalDevice(RunOnPhysicalDevice),
     RunOnSimulators(RunOnSimulators),
-    GetProjectMetadata(GetProjectMetadata),
     SetBenchArg(SetBenchArg),
 }
 
@@ -45,7 +46,6 @@ impl crate::common::task::Task<Context> for Task {
             Task::CreateSignedBundles(task) => task.run(context),
             Task::RunOnPhysicalDevice(task) => task.run(context),
             Task::RunOnSimulators(task) => task.run(context),
-            Task::GetProjectMetadata(task) => task.run(context),
             Task::SetBenchArg(task) => task.run(context),
         }
     }
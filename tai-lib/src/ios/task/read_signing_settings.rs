@@ -5,7 +5,7 @@ use cfg_expr::targets::TargetInfo;
 use tracing::instrument;
 
 use crate::{
-    common::{opts::Options, task::Task},
+    common::{command::Platform, opts::Options, task::Task},
     ios::bundle::signing::find_signing_settings,
     TaiResult,
 };
@@ -50,10 +50,10 @@ impl TryFrom<&TargetInfo<'_>> for Sdk {
     type Error = anyhow::Error;
 
     fn try_from(value: &TargetInfo<'_>) -> Result<Self, Self::Error> {
-        match value.triple {
-            "aarch64-apple-ios" => Ok(Sdk::IPhoneOS),
-            "x86_64-apple-ios" => Ok(Sdk::IPhoneSimulator),
-            _ => bail!("unsupported target"),
+        match Platform::of(value) {
+            Some(Platform::IosDevice) => Ok(Sdk::IPhoneOS),
+            Some(Platform::IosSimulator) => Ok(Sdk::IPhoneSimulator),
+            _ => bail!("unsupported target: {}", value.triple),
         }
     }
 }
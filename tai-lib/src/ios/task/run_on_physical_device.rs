@@ -1,13 +1,14 @@
-use std::{fs::create_dir_all, path::Path};
+use std::{fs::create_dir_all, path::Path, time::Duration};
 
-use anyhow::bail;
-use tracing::{info, instrument};
+use tracing::{info, instrument, warn};
 
 use crate::{
     common::{
         opts::{BinaryOptions, Options},
         project::ProjectMetadata,
+        report::{BundleResult, Event, Reporter, RunReport, RunStatus},
         task::Task,
+        timeout::Timeouts,
         tools::Rsync,
     },
     ios::{
@@ -26,7 +27,7 @@ pub struct RunOnPhysicalDevice;
 
 impl Task<Context> for RunOnPhysicalDevice {
     #[instrument(name = "run_on_physical_device", skip(self, context))]
-    fn run(&self, context: Context) -> TaiResult<Context> {
+    fn run(&self, mut context: Context) -> TaiResult<Context> {
         let provisioned_devices = &context
             .get::<SigningSettings>()
             .mobile_provision
@@ -40,32 +41,54 @@ impl Task<Context> for RunOnPhysicalDevice {
             None => &default,
         };
 
+        let reporter: &Reporter = context.get();
+        if opts.run.artifacts.is_some() {
+            warn!("`--artifacts` is not supported on physical iOS devices");
+        }
+
         let app_deltas = ios_cache.join("app_deltas");
         create_dir_all(&app_deltas)?;
 
-        context
+        let mut report = RunReport::default();
+        'devices: for provisioned_device in context
             .get::<PhysicalDevices>()
             .0
             .iter()
             .filter(|device| provisioned_devices.contains(&device.id))
-            .try_for_each(|provisioned_device| {
-                bundles.bundles.iter().try_for_each(|bundle| {
-                    let mut cmd = Rsync::new(&bundle.root, ios_cache);
-                    cmd.archive().delete();
-                    if opts.cli.verbose {
-                        cmd.verbose();
-                    }
-                    cmd.execute()?;
+        {
+            let timeouts = Timeouts::start(binary_opts, &opts.run);
+            for bundle in &bundles.bundles {
+                let result =
+                    BundleResult::measure(&provisioned_device.id, &bundle.build_unit.name, || {
+                        if timeouts.device_expired() {
+                            return Ok(RunStatus::TimedOut);
+                        }
+                        let mut cmd = Rsync::new(&bundle.root, ios_cache);
+                        cmd.archive().delete();
+                        if opts.cli.verbose {
+                            cmd.verbose();
+                        }
+                        cmd.execute()?;
+
+                        install_and_launch(
+                            &provisioned_device.id,
+                            ios_cache.join(format!("{}.app", APP_DISPLAY_NAME)),
+                            &app_deltas,
+                            binary_opts,
+                            timeouts.next_bundle(),
+                            opts.cli.verbose,
+                        )
+                    });
+                reporter.emit(&Event::BundleFinished(result.clone()));
+                let passed = result.status.is_success();
+                report.push(result);
+                if !passed && !opts.run.no_fail_fast {
+                    break 'devices;
+                }
+            }
+        }
 
-                    install_and_launch(
-                        &provisioned_device.id,
-                        ios_cache.join(format!("{}.app", APP_DISPLAY_NAME)),
-                        &app_deltas,
-                        binary_opts,
-                        opts.cli.verbose,
-                    )
-                })
-            })?;
+        context.insert(report);
         Ok(context)
     }
 }
@@ -76,20 +99,22 @@ fn install_and_launch<P1, P2>(
     bundle_root: P1,
     app_deltas: P2,
     binary_opt: &BinaryOptions,
+    timeout: Option<Duration>,
     verbose: bool,
-) -> TaiResult<()>
+) -> TaiResult<RunStatus>
 where
     P1: AsRef<Path>,
     P2: AsRef<Path>,
 {
+    let args = binary_opt.binary_args();
     let mut cmd = IosDeployLaunch::new(device, &bundle_root);
     cmd.non_interactive()
         .no_wifi()
         .debug()
         .app_deltas(app_deltas);
 
-    if let Some(ref args) = binary_opt.args {
-        cmd.args(args);
+    if !args.is_empty() {
+        cmd.args(&args);
     }
     if let Some(ref envs) = binary_opt.envs {
         cmd.envs(envs);
@@ -97,19 +122,30 @@ where
     if verbose {
         cmd.verbose();
     }
+    if let Some(timeout) = timeout {
+        cmd.timeout(timeout);
+    }
 
-    match cmd.execute() {
-        Ok(_) => {
-            info!("test result ok");
-            Ok(())
-        }
-        Err(err) => {
-            bail!(
-                "test {} {} failed with: {}",
-                APP_ID,
-                &bundle_root.as_ref().display(),
-                err
-            )
+    let status = match cmd.execute()? {
+        Some(status) => status,
+        None => {
+            warn!(
+                "{} did not finish within {:?}",
+                bundle_root.as_ref().display(),
+                timeout.unwrap_or_default()
+            );
+            return Ok(RunStatus::TimedOut);
         }
+    };
+    if status.success() {
+        info!("test result ok");
+    } else {
+        info!(
+            "test {} {} failed with: {}",
+            APP_ID,
+            &bundle_root.as_ref().display(),
+            status
+        );
     }
+    Ok(RunStatus::from_exit_code(status.code()))
 }
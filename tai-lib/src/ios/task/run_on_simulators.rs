@@ -1,21 +1,26 @@
 use std::{
-    fs::File,
-    io::{BufRead, BufReader, Write},
+    fs::{self, File},
+    io::Write,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
-use anyhow::{anyhow, bail, Error};
+use anyhow::{anyhow, Context as _, Error};
 use once_cell::sync::OnceCell;
 use regex::Regex;
 use simctl::{get_app_container::Container, Device};
 use tempfile::TempDir;
-use tracing::{debug, info, instrument};
+use tracing::{debug, info, instrument, warn};
+use walkdir::WalkDir;
 
 use crate::{
     common::{
-        bundle::BuiltBundles,
+        bundle::{BuiltBundle, BuiltBundles},
         opts::{BinaryOptions, Options},
+        report::{BundleResult, Event, Reporter, RunReport, RunStatus},
         task::Task,
+        timeout::Timeouts,
+        tools::follow::follow_file_while,
     },
     ios::{
         platform::APP_ID,
@@ -30,7 +35,7 @@ pub struct RunOnSimulators;
 
 impl Task<Context> for RunOnSimulators {
     #[instrument(name = "run_on_simulator", skip(self, context))]
-    fn run(&self, context: Context) -> TaiResult<Context> {
+    fn run(&self, mut context: Context) -> TaiResult<Context> {
         let bundles: &BuiltBundles = context.get();
         let opts: &Options = context.get();
         let default = BinaryOptions::default();
@@ -38,28 +43,48 @@ impl Task<Context> for RunOnSimulators {
             Some(opts) => opts,
             None => &default,
         };
+        let reporter: &Reporter = context.get();
 
-        context
-            .get::<Simulators>()
-            .0
-            .iter()
-            .try_for_each(|simulator| {
-                bundles
-                    .bundles
-                    .iter()
-                    .try_for_each(|bundle| install_and_launch(simulator, &bundle.root, binary_opt))
-            })?;
+        let mut report = RunReport::default();
+        'simulators: for simulator in &context.get::<Simulators>().0 {
+            let timeouts = Timeouts::start(binary_opt, &opts.run);
+            for bundle in &bundles.bundles {
+                let result =
+                    BundleResult::measure(&simulator.udid, &bundle.build_unit.name, || {
+                        if timeouts.device_expired() {
+                            return Ok(RunStatus::TimedOut);
+                        }
+                        install_and_launch(
+                            simulator,
+                            bundle,
+                            binary_opt,
+                            timeouts.next_bundle(),
+                            reporter,
+                        )
+                    });
+                reporter.emit(&Event::BundleFinished(result.clone()));
+                let passed = result.status.is_success();
+                report.push(result);
+                if !passed && !opts.run.no_fail_fast {
+                    break 'simulators;
+                }
+            }
+        }
+
+        context.insert(report);
         Ok(context)
     }
 }
 
-#[instrument(name = "install_launch", fields(device = %device.udid), skip(bundle_root))]
-fn install_and_launch<P: AsRef<Path>>(
+#[instrument(name = "install_launch", fields(device = %device.udid), skip(bundle, reporter))]
+fn install_and_launch(
     device: &Device,
-    bundle_root: P,
+    bundle: &BuiltBundle,
     binary_opt: &BinaryOptions,
-) -> TaiResult<()> {
-    let bundle_root = bundle_root.as_ref();
+    timeout: Option<Duration>,
+    reporter: &Reporter,
+) -> TaiResult<RunStatus> {
+    let bundle_root = &bundle.root;
     info!("uninstall app with app id: {}", APP_ID);
     device
         .uninstall(APP_ID)
@@ -71,23 +96,57 @@ fn install_and_launch<P: AsRef<Path>>(
         .map_err(|_| anyhow!("failed to install: {}", APP_ID))?;
 
     info!("launch app with app id:: {}", APP_ID);
-    match launch_app(device, binary_opt)? {
-        0 => {
-            info!("test result ok");
-            Ok(())
+    let exit_code = launch_app(
+        device,
+        &bundle.build_unit.name,
+        binary_opt,
+        timeout,
+        reporter,
+    );
+
+    if let Some((remote_dir, local_dir)) = reporter.artifacts(&device.udid, &bundle.build_unit.name)
+    {
+        if let Err(err) = copy_artifacts(device, remote_dir, &local_dir) {
+            warn!(
+                "failed to copy the artifacts {}: {:#}",
+                remote_dir.display(),
+                err
+            );
         }
-        ec => {
-            bail!(
-                "test {} {} failed with exit code: {}",
-                APP_ID,
-                bundle_root.display(),
-                ec
-            )
+    }
+
+    let exit_code = match exit_code? {
+        Some(exit_code) => exit_code,
+        None => {
+            warn!(
+                "{} did not finish within {:?}",
+                bundle.build_unit.name,
+                timeout.unwrap_or_default()
+            );
+            return Ok(RunStatus::TimedOut);
         }
+    };
+    match exit_code {
+        0 => info!("test result ok"),
+        ec => info!(
+            "test {} {} failed with exit code: {}",
+            APP_ID,
+            bundle_root.display(),
+            ec
+        ),
     }
+    Ok(RunStatus::from_exit_code(i32::try_from(exit_code).ok()))
 }
 
-fn launch_app(device: &Device, binary_opt: &BinaryOptions) -> TaiResult<u32> {
+/// Launches the app and waits until it exits. Returns `None` if the app was terminated
+/// because it did not exit within `timeout`.
+fn launch_app(
+    device: &Device,
+    bundle_name: &str,
+    binary_opt: &BinaryOptions,
+    timeout: Option<Duration>,
+    reporter: &Reporter,
+) -> TaiResult<Option<u32>> {
     let install_path = device
         .get_app_container(APP_ID, &Container::App)
         .map_err(|err| anyhow!("{:?}", err))?;
@@ -99,19 +158,59 @@ fn launch_app(device: &Device, binary_opt: &BinaryOptions) -> TaiResult<u32> {
         &device.udid,
         APP_ID,
         &stdout_str,
-        &binary_opt.args,
+        &binary_opt.binary_args(),
         &binary_opt.envs,
     )?;
     debug!("app pid: {}", app_pid);
     let (lldb_path, guard) = create_lldb_script(&app_pid)?;
-    let output = lldb::run_source(&lldb_path)?;
+    // stream the output of the app while lldb waits for it to exit
+    let (output, app_output) = follow_file_while(
+        &stdout,
+        |chunk| {
+            let mut out = reporter.human_output();
+            let _ = out.write_all(chunk).and_then(|_| out.flush());
+        },
+        || lldb::run_source(&lldb_path, timeout),
+    );
+    let app_output = app_output?;
+    guard.close()?; // delete lldb script
 
-    let stdout_file = File::open(stdout)?;
-    let mut reader = BufReader::new(stdout_file);
-    std::io::stdout().write_all(reader.fill_buf()?)?;
+    match output? {
+        Some(output) => {
+            reporter.write_junit(&device.udid, bundle_name, &app_output);
+            extract_lldb_exit_status(&output.stdout).map(Some)
+        }
+        None => {
+            xcrun::terminate_app(&device.udid, APP_ID)?;
+            Ok(None)
+        }
+    }
+}
 
-    guard.close()?; // delete lldb script
-    extract_lldb_exit_status(&output.stdout)
+/// Copies `remote_dir` of the `Documents` directory of the app to `local_dir`.
+fn copy_artifacts(device: &Device, remote_dir: &Path, local_dir: &Path) -> TaiResult<()> {
+    let data_container = device
+        .get_app_container(APP_ID, &Container::Data)
+        .map_err(|err| anyhow!("{:?}", err))?;
+    let from = data_container.join("Documents").join(remote_dir);
+    debug!(
+        "copy artifacts from: {} to: {}",
+        from.display(),
+        local_dir.display()
+    );
+
+    for entry in WalkDir::new(&from) {
+        let entry = entry.with_context(|| format!("Failed to read {}", from.display()))?;
+        let to = local_dir.join(entry.path().strip_prefix(&from)?);
+        match entry.file_type().is_dir() {
+            true => fs::create_dir_all(&to)?,
+            false => {
+                fs::copy(entry.path(), &to)
+                    .with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+            }
+        }
+    }
+    Ok(())
 }
 
 fn create_lldb_script(app_pid: &str) -> Result<(PathBuf, TempDir), Error> {
@@ -1,9 +1,12 @@
 use std::{
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::{Command, ExitStatus, Stdio},
+    time::Duration,
 };
 
-use crate::{common::tools::command_ext::ExitStatusExt, TaiResult};
+use anyhow::anyhow;
+
+use crate::{common::tools::command_ext::ChildExt, TaiResult};
 
 const IOS_DEPLOY: &str = "ios-deploy";
 
@@ -17,6 +20,7 @@ pub struct IosDeployLaunch<'a, 'e> {
     no_wifi: bool,
     verbose: bool,
     app_deltas: Option<PathBuf>,
+    timeout: Option<Duration>,
 }
 
 impl<'a, 'e> IosDeployLaunch<'a, 'e> {
@@ -31,6 +35,7 @@ impl<'a, 'e> IosDeployLaunch<'a, 'e> {
             no_wifi: false,
             verbose: false,
             app_deltas: None,
+            timeout: None,
         }
     }
 
@@ -68,7 +73,17 @@ impl<'a, 'e> IosDeployLaunch<'a, 'e> {
         self
     }
 
-    pub fn execute(&mut self) -> TaiResult<()> {
+    /// Kills `ios-deploy` if it is still running after `timeout`. In `debug` mode,
+    /// this ends the debug session and with it the app.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Runs `ios-deploy` and returns its exit status which, in `debug` mode, is the
+    /// exit status of the app. Returns `None` if `ios-deploy` was killed because of
+    /// the `timeout`.
+    pub fn execute(&mut self) -> TaiResult<Option<ExitStatus>> {
         let mut cmd = Command::new(IOS_DEPLOY);
         if !self.verbose {
             cmd.stdout(Stdio::null());
@@ -84,7 +99,7 @@ impl<'a, 'e> IosDeployLaunch<'a, 'e> {
         self.no_wifi.then_some(()).map(|_| cmd.arg("--no-wifi"));
 
         if let Some(args) = self.args {
-            cmd.args(["--args", &args.join(" ")]);
+            cmd.args(["--args", &join_args(args)]);
         };
 
         if let Some(envs) = self.envs {
@@ -102,6 +117,54 @@ impl<'a, 'e> IosDeployLaunch<'a, 'e> {
 
         cmd.arg("--bundle").arg(&self.bundle);
 
-        cmd.status()?.expect_success("failed to run ios_deploy")
+        cmd.spawn()
+            .and_then(|mut child| child.wait_timeout(self.timeout))
+            .map_err(|err| anyhow!("failed to run ios_deploy: {}", err))
+    }
+}
+
+/// Joins the arguments for `--args`. ios-deploy inserts them into a single-quoted Python string
+/// of its lldb script, which splits them with `shlex.split`. Arguments that are not plain words
+/// are therefore quoted for `shlex` and escaped for the Python string.
+fn join_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| quote_arg(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn quote_arg(arg: &str) -> String {
+    let is_word = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_=.,:/+@%".contains(c));
+    if is_word {
+        return arg.to_string();
+    }
+    let shlex_quoted = format!("\"{}\"", arg.replace('\\', r"\\").replace('"', r#"\""#));
+    shlex_quoted.replace('\\', r"\\").replace('\'', r"\'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_args() {
+        let args = [
+            "--bench",
+            "--skip",
+            "tests::a b",
+            "it's",
+            r#"say "hi""#,
+            r"C:\dir",
+            "",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            join_args(&args),
+            r#"--bench --skip "tests::a b" "it\'s" "say \\"hi\\"" "C:\\\\dir" """#
+        );
     }
 }
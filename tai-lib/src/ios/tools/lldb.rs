@@ -1,18 +1,24 @@
 use std::{
     path::Path,
     process::{Command, Output},
+    time::Duration,
 };
 
 use anyhow::anyhow;
 
-use crate::TaiResult;
+use crate::{common::tools::command_ext::CommandExt, TaiResult};
 
 const LLDB: &str = "lldb";
 
-pub fn run_source<P: AsRef<Path>>(source: P) -> TaiResult<Output> {
+/// Runs an lldb script. Returns `None` if lldb was killed because it did not finish
+/// within `timeout`.
+pub fn run_source<P: AsRef<Path>>(
+    source: P,
+    timeout: Option<Duration>,
+) -> TaiResult<Option<Output>> {
     Command::new(LLDB)
         .arg("-s")
         .arg(source.as_ref())
-        .output()
+        .output_streamed(timeout, |_, _| {})
         .map_err(|err| anyhow!("{}", err))
 }
@@ -1,9 +1,13 @@
-use std::process::Command;
+use std::{
+    collections::HashMap,
+    process::{Command, Stdio},
+};
 
 use anyhow::anyhow;
+use serde::Deserialize;
 use simctl::{list::DeviceState, Device, DeviceQuery, Simctl};
 
-use crate::TaiResult;
+use crate::{common::tools::command_ext::ExitStatusExt, TaiResult};
 
 const XCRUN: &str = "xcrun";
 
@@ -11,23 +15,11 @@ pub fn launch_app(
     dev_id: &str,
     app_id: &str,
     stdout: &str,
-    args: &Option<Vec<String>>,
+    args: &[String],
     envs: &Option<Vec<(String, String)>>,
 ) -> TaiResult<String> {
     let mut cmd = Command::new(XCRUN);
-
-    cmd.args([
-        "simctl",
-        "launch",
-        &format!("--stdout={}", stdout),
-        "-w",
-        dev_id,
-        app_id,
-    ]);
-
-    if let Some(args) = args {
-        cmd.args(args);
-    }
+    cmd.args(launch_args(dev_id, app_id, stdout, args));
 
     if let Some(envs) = envs {
         cmd.envs(
@@ -46,6 +38,155 @@ pub fn launch_app(
         .to_string())
 }
 
+/// The arguments of `xcrun simctl launch`. The arguments of the app are passed as they are,
+/// simctl does not parse them.
+fn launch_args(dev_id: &str, app_id: &str, stdout: &str, args: &[String]) -> Vec<String> {
+    [
+        "simctl",
+        "launch",
+        &format!("--stdout={}", stdout),
+        "-w",
+        dev_id,
+        app_id,
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .chain(args.iter().cloned())
+    .collect()
+}
+
+pub fn terminate_app(dev_id: &str, app_id: &str) -> TaiResult<()> {
+    Command::new(XCRUN)
+        .args(["simctl", "terminate", dev_id, app_id])
+        .status()?
+        .expect_success("failed to terminate app")
+}
+
+/// The output of `xcrun simctl list --json`.
+#[derive(Debug, Default, Deserialize)]
+pub struct SimulatorList {
+    #[serde(default)]
+    pub devicetypes: Vec<DeviceType>,
+    #[serde(default)]
+    pub runtimes: Vec<Runtime>,
+    /// The simulators by the identifier of their runtime
+    pub devices: HashMap<String, Vec<SimulatorDevice>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceType {
+    /// e.g. `iPhone 14`
+    pub name: String,
+    /// e.g. `com.apple.CoreSimulator.SimDeviceType.iPhone-14`
+    pub identifier: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Runtime {
+    /// e.g. `iOS 16.2`
+    pub name: String,
+    /// e.g. `com.apple.CoreSimulator.SimRuntime.iOS-16-2`
+    pub identifier: String,
+    /// e.g. `16.2`
+    pub version: String,
+    #[serde(default)]
+    pub is_available: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatorDevice {
+    pub udid: String,
+    /// e.g. `Booted` or `Shutdown`
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub is_available: bool,
+    #[serde(default)]
+    pub device_type_identifier: String,
+}
+
+/// Lists the device types, runtimes and simulators.
+pub fn list_simulators() -> TaiResult<SimulatorList> {
+    let output = Command::new(XCRUN)
+        .args(["simctl", "list", "--json"])
+        .output()?;
+    output
+        .status
+        .expect_success("failed to list the simulators")?;
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Creates a simulator and returns its UDID.
+pub fn create_simulator(name: &str, device_type: &str, runtime: &str) -> TaiResult<String> {
+    let output = Command::new(XCRUN)
+        .args(["simctl", "create", name, device_type, runtime])
+        .stderr(Stdio::inherit())
+        .output()?;
+    output
+        .status
+        .expect_success("failed to create the simulator")?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Boots the simulator, if it is not booted yet, and waits until it has finished booting.
+pub fn boot_simulator(udid: &str) -> TaiResult<()> {
+    Command::new(XCRUN)
+        .args(["simctl", "bootstatus", udid, "-b"])
+        .stderr(Stdio::inherit())
+        .output()?
+        .status
+        .expect_success("failed to boot the simulator")
+}
+
+pub fn shutdown_simulator(udid: &str) -> TaiResult<()> {
+    Command::new(XCRUN)
+        .args(["simctl", "shutdown", udid])
+        .status()?
+        .expect_success("failed to shut down the simulator")
+}
+
+pub fn delete_simulator(udid: &str) -> TaiResult<()> {
+    Command::new(XCRUN)
+        .args(["simctl", "delete", udid])
+        .status()?
+        .expect_success("failed to delete the simulator")
+}
+
+/// Returns the OS version (e.g. `iOS 16.2`) of every booted simulator by UDID.
+pub fn booted_simulator_runtimes() -> TaiResult<HashMap<String, String>> {
+    let output = Command::new(XCRUN)
+        .args(["simctl", "list", "devices", "booted", "--json"])
+        .output()?;
+    output
+        .status
+        .expect_success("failed to list the booted simulators")?;
+
+    let list: SimulatorList = serde_json::from_slice(&output.stdout)?;
+    Ok(list
+        .devices
+        .into_iter()
+        .flat_map(|(runtime, devices)| {
+            let runtime = runtime_name(&runtime);
+            devices
+                .into_iter()
+                .map(move |device| (device.udid, runtime.clone()))
+        })
+        .collect())
+}
+
+/// `com.apple.CoreSimulator.SimRuntime.iOS-16-2` -> `iOS 16.2`
+fn runtime_name(identifier: &str) -> String {
+    let runtime = identifier
+        .strip_prefix("com.apple.CoreSimulator.SimRuntime.")
+        .unwrap_or(identifier);
+    match runtime.split_once('-') {
+        Some((os, version)) => format!("{} {}", os, version.replace('-', ".")),
+        None => runtime.to_string(),
+    }
+}
+
 pub fn list_booted_simulators() -> TaiResult<Vec<Device>> {
     let simctl = Simctl::new();
     let devices = simctl.list().map_err(|err| anyhow!("{:?}", err))?;
@@ -57,3 +198,39 @@ pub fn list_booted_simulators() -> TaiResult<Vec<Device>> {
         .cloned()
         .collect())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launch_args() {
+        let args = ["--skip".to_string(), "tests::a b; $HOME".to_string()];
+        assert_eq!(
+            launch_args("UDID", "rs.tai", "/tmp/stdout", &args),
+            vec![
+                "simctl",
+                "launch",
+                "--stdout=/tmp/stdout",
+                "-w",
+                "UDID",
+                "rs.tai",
+                "--skip",
+                "tests::a b; $HOME"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_runtime_name() {
+        assert_eq!(
+            runtime_name("com.apple.CoreSimulator.SimRuntime.iOS-16-2"),
+            "iOS 16.2"
+        );
+        assert_eq!(
+            runtime_name("com.apple.CoreSimulator.SimRuntime.watchOS-9-1"),
+            "watchOS 9.1"
+        );
+        assert_eq!(runtime_name("iOS"), "iOS");
+    }
+}
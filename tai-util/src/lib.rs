@@ -2,7 +2,14 @@ use std::{env, path::PathBuf};
 
 // cannot use resources https://stackoverflow.com/questions/29271548/code-sign-error-bundle-format-unrecognized-invalid-or-unsuitable
 pub const DATA_DIR_NAME: &str = "test-data";
+pub const ARTIFACTS_DIR_NAME: &str = "artifacts";
 
+pub use dirs::artifacts_dir;
+
+/// Returns the path of a resource that was included with `-r <id>=<path>`.
+///
+/// `test_data_id` can also point into a resource directory, e.g. `fixtures/a/b.json`
+/// for `-r fixtures=./data`.
 pub fn resources_file_path(test_data_id: &str) -> PathBuf {
     try_resources_file_path(test_data_id)
         .unwrap_or_else(|| panic!("couldn't find test data {}", test_data_id))
@@ -15,12 +22,259 @@ pub fn try_resources_file_path(test_data_id: &str) -> Option<PathBuf> {
         current_exe
             .parent()
             .map(|p| p.join(DATA_DIR_NAME))
-            .map(|p| p.join(test_data_id))
+            .map(|p| join_resource_path(p, test_data_id))
     } else {
         None
     }
 }
 
+/// Joins the `/`-separated components of `test_data_id` to `root`.
+fn join_resource_path(root: PathBuf, test_data_id: &str) -> PathBuf {
+    test_data_id
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .fold(root, |path, component| path.join(component))
+}
+
+pub mod dirs {
+    //! Writable directories that can be used the same way on the device and on the host.
+    //!
+    //! |           | Android                | iOS                           | host                                          |
+    //! |-----------|------------------------|-------------------------------|-----------------------------------------------|
+    //! | data      | `<bundle>/data`        | `Library/Application Support` | `target/cargo-tai/host/data`                  |
+    //! | cache     | `<bundle>/cache`       | `Library/Caches`              | `target/cargo-tai/host/cache`                 |
+    //! | artifacts | `<bundle>/artifacts`   | `Documents/artifacts`         | `target/cargo-tai/artifacts/host/<binary>`    |
+    //!
+    //! The directories are created if they do not exist. On Android, they are located in the
+    //! bundle root and are deleted together with the bundle after the run.
+    use std::{fs, path::PathBuf};
+
+    /// Returns the directory for data that the binary creates.
+    pub fn data_dir() -> PathBuf {
+        create(platform::data_dir())
+    }
+
+    /// Returns the directory for data that can be recreated.
+    pub fn cache_dir() -> PathBuf {
+        create(platform::cache_dir())
+    }
+
+    /// Returns the directory for the artifacts of a test or benchmark, e.g. reports.
+    ///
+    /// `cargo-tai --artifacts artifacts` copies it back from the device into
+    /// `target/cargo-tai/artifacts/<device>/<bundle>`.
+    pub fn artifacts_dir() -> PathBuf {
+        create(platform::artifacts_dir())
+    }
+
+    fn create(dir: PathBuf) -> PathBuf {
+        fs::create_dir_all(&dir)
+            .unwrap_or_else(|err| panic!("couldn't create {}: {}", dir.display(), err));
+        dir
+    }
+
+    #[cfg(target_os = "android")]
+    mod platform {
+        pub use crate::android::{artifacts_dir, cache_dir, data_dir};
+    }
+
+    #[cfg(target_os = "ios")]
+    mod platform {
+        use std::path::PathBuf;
+
+        use crate::{ios, ARTIFACTS_DIR_NAME};
+
+        pub fn data_dir() -> PathBuf {
+            ios::user_application_support()
+        }
+
+        pub fn cache_dir() -> PathBuf {
+            ios::user_cache()
+        }
+
+        pub fn artifacts_dir() -> PathBuf {
+            ios::user_documents().join(ARTIFACTS_DIR_NAME)
+        }
+    }
+
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    mod platform {
+        use std::{
+            env,
+            path::{Path, PathBuf},
+        };
+
+        use crate::ARTIFACTS_DIR_NAME;
+
+        pub fn data_dir() -> PathBuf {
+            tai_target_dir().join("host").join("data")
+        }
+
+        pub fn cache_dir() -> PathBuf {
+            tai_target_dir().join("host").join("cache")
+        }
+
+        pub fn artifacts_dir() -> PathBuf {
+            let exe = current_exe();
+            let name = exe.file_stem().unwrap_or_default();
+            tai_target_dir()
+                .join(ARTIFACTS_DIR_NAME)
+                .join("host")
+                .join(name)
+        }
+
+        fn tai_target_dir() -> PathBuf {
+            let target_dir = env::var_os("CARGO_TARGET_DIR")
+                .map(PathBuf::from)
+                .or_else(|| find_target_dir(&current_exe()))
+                .or_else(|| {
+                    env::var_os("CARGO_MANIFEST_DIR").map(|dir| Path::new(&dir).join("target"))
+                })
+                .unwrap_or_else(|| PathBuf::from("target"));
+            target_dir.join("cargo-tai")
+        }
+
+        fn current_exe() -> PathBuf {
+            env::current_exe().expect("current exe path not accessible")
+        }
+
+        /// Cargo marks its target directory with a `CACHEDIR.TAG`.
+        pub(super) fn find_target_dir(exe: &Path) -> Option<PathBuf> {
+            exe.ancestors()
+                .skip(1)
+                .find(|dir| dir.join("CACHEDIR.TAG").is_file())
+                .map(Path::to_path_buf)
+        }
+    }
+
+    #[cfg(all(test, not(any(target_os = "android", target_os = "ios"))))]
+    mod tests {
+        use std::fs;
+
+        use super::*;
+
+        #[test]
+        fn test_find_target_dir() {
+            let dir = tempfile::tempdir().unwrap();
+            let target = dir.path().join("target");
+            let deps = target.join("x86_64-unknown-linux-gnu/debug/deps");
+            fs::create_dir_all(&deps).unwrap();
+            fs::write(target.join("CACHEDIR.TAG"), "").unwrap();
+
+            assert_eq!(
+                platform::find_target_dir(&deps.join("integration-1234")),
+                Some(target)
+            );
+            assert_eq!(
+                platform::find_target_dir(&dir.path().join("bin/integration")),
+                None
+            );
+        }
+
+        #[test]
+        fn test_host_dirs() {
+            let artifacts_dir = artifacts_dir();
+            assert!(artifacts_dir.is_dir());
+            assert!(artifacts_dir.ends_with(
+                std::path::Path::new("cargo-tai/artifacts/host")
+                    .join(std::env::current_exe().unwrap().file_stem().unwrap())
+            ));
+            assert!(data_dir().ends_with("cargo-tai/host/data"));
+        }
+    }
+}
+
+#[cfg(feature = "criterion")]
+pub mod criterion {
+    //! Runs criterion benchmarks on the device. Requires the `criterion` feature.
+    //!
+    //! ```ignore
+    //! use criterion::{criterion_group, Criterion};
+    //!
+    //! fn criterion_benchmark(c: &mut Criterion) {
+    //!     c.bench_function("fib 20", |b| b.iter(|| fibonacci(20)));
+    //! }
+    //!
+    //! criterion_group!(benches, criterion_benchmark);
+    //! tai_util::criterion_main_tai!(benches);
+    //! ```
+    //!
+    //! The reports are written into [`output_dir`], which `cargo-tai --artifacts` copies back into
+    //! `target/cargo-tai/artifacts/<device>/<bundle>/criterion`.
+    use std::{env, path::PathBuf};
+
+    #[doc(hidden)]
+    pub use ::criterion as __criterion;
+
+    use crate::dirs;
+
+    /// The directory in [`dirs::artifacts_dir`] into which criterion writes its reports.
+    pub const CRITERION_DIR_NAME: &str = "criterion";
+
+    /// Returns the directory into which criterion writes its reports: `CRITERION_HOME` if it is
+    /// set, otherwise `<artifacts dir>/criterion`.
+    pub fn output_dir() -> PathBuf {
+        env::var_os("CRITERION_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| dirs::artifacts_dir().join(CRITERION_DIR_NAME))
+    }
+
+    /// Points `CRITERION_HOME` at [`output_dir`].
+    pub fn setup() {
+        env::set_var("CRITERION_HOME", output_dir());
+    }
+
+    /// Like `criterion::criterion_main!`, but writes the reports into [`output_dir`].
+    ///
+    /// The arguments of the binary, e.g. the `--bench` that cargo-tai passes to benchmarks,
+    /// are parsed by criterion.
+    #[macro_export]
+    macro_rules! criterion_main_tai {
+        ( $( $group:path ),+ $(,)* ) => {
+            fn main() {
+                $crate::criterion::setup();
+
+                $(
+                    $group();
+                )+
+
+                $crate::criterion::__criterion::Criterion::default()
+                    .configure_from_args()
+                    .final_summary();
+            }
+        };
+    }
+}
+
+#[cfg(target_os = "android")]
+pub mod android {
+    //! The directories in the root of the bundle on the device, which is the working directory
+    //! of the binary. The bundle is deleted after the run.
+    use std::{env, path::PathBuf};
+
+    use crate::ARTIFACTS_DIR_NAME;
+
+    pub fn bundle_root() -> PathBuf {
+        env::current_exe()
+            .expect("current exe path not accessible")
+            .parent()
+            .expect("exe without parent")
+            .to_path_buf()
+    }
+
+    pub fn data_dir() -> PathBuf {
+        bundle_root().join("data")
+    }
+
+    pub fn cache_dir() -> PathBuf {
+        bundle_root().join("cache")
+    }
+
+    pub fn artifacts_dir() -> PathBuf {
+        bundle_root().join(ARTIFACTS_DIR_NAME)
+    }
+}
+
 #[cfg(target_os = "ios")]
 pub mod ios {
     //! https://docs.microsoft.com/de-de/xamarin/ios/app-fundamentals/file-system#application-directories